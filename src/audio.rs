@@ -0,0 +1,117 @@
+use ggez::audio::{self, SoundSource};
+use ggez::{Context, GameResult};
+use ggez::nalgebra as na;
+
+// something that happened in the game worth hearing about
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sound {
+    Pickup,
+    Crash,
+    Pop,
+    Start,
+    Title
+}
+
+// owns every sound source so gameplay code only has to queue up a `Sound`
+pub struct Audio {
+    pickup: audio::Source,
+    crash: audio::Source,
+    pop: audio::Source,
+    start: audio::Source,
+    title: audio::Source,
+    queue: Vec<Sound>,
+    pub volume: f32
+}
+
+impl Audio {
+    pub fn new(ctx: &mut Context) -> GameResult<Audio> {
+        Ok(Audio {
+            pickup: audio::Source::new(ctx, "/pickup.wav")?,
+            crash: audio::Source::new(ctx, "/crash.wav")?,
+            pop: audio::Source::new(ctx, "/pop.wav")?,
+            start: audio::Source::new(ctx, "/start.wav")?,
+            title: audio::Source::new(ctx, "/title.wav")?,
+            queue: Vec::<Sound>::new(),
+            volume: 1.0
+        })
+    }
+
+    // the same sound queued twice in one step only plays once
+    pub fn queue(&mut self, sound: Sound) {
+        if !self.queue.contains(&sound) {
+            self.queue.push(sound);
+        }
+    }
+
+    pub fn queued(&self) -> &[Sound] {
+        &self.queue
+    }
+
+    pub fn play(&mut self) -> GameResult {
+        for sound in self.queue.drain(..) {
+            let source = match sound {
+                Sound::Pickup => &mut self.pickup,
+                Sound::Crash => &mut self.crash,
+                Sound::Pop => &mut self.pop,
+                Sound::Start => &mut self.start,
+                Sound::Title => &mut self.title
+            };
+            source.set_volume(self.volume);
+            source.play_detached()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Track {
+    Title,
+    Game
+}
+
+// ggez only applies pitch when a source starts playing, so rather than
+// retuning the game track as the snake speeds up a looping drum layer
+// recorded in time with it is faded in
+pub struct Music {
+    title: audio::Source,
+    game: audio::Source,
+    drive: audio::Source,
+    // 0.0 is all title track, 1.0 all game track
+    mix: f32,
+    pub volume: f32
+}
+
+impl Music {
+    pub fn new(ctx: &mut Context) -> GameResult<Music> {
+        let mut music = Music {
+            title: audio::Source::new(ctx, "/music_title.wav")?,
+            game: audio::Source::new(ctx, "/music_game.wav")?,
+            drive: audio::Source::new(ctx, "/music_drive.wav")?,
+            mix: 0.0,
+            volume: 1.0
+        };
+        for source in [&mut music.title, &mut music.game, &mut music.drive]
+                .iter_mut() {
+            source.set_repeat(true);
+            source.set_volume(0.0);
+            source.play()?;
+        }
+        music.update(Track::Title, 0.0);
+        Ok(music)
+    }
+
+    // crossfade towards `track` over about a second, with the drums
+    // following `intensity` from 0.0 to 1.0
+    pub fn update(&mut self, track: Track, intensity: f32) {
+        let target = match track {
+            Track::Title => 0.0,
+            Track::Game => 1.0
+        };
+        self.mix += na::clamp(target - self.mix, -1.0 / 60.0, 1.0 / 60.0);
+
+        let drive = self.mix * na::clamp(intensity, 0.0, 1.0);
+        self.title.set_volume(self.volume * 0.6 * (1.0 - self.mix));
+        self.game.set_volume(self.volume * 0.6 * self.mix);
+        self.drive.set_volume(self.volume * 0.8 * drive);
+    }
+}
@@ -0,0 +1,132 @@
+use ggez::{conf, filesystem, graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::graph::RunGraph;
+use crate::UPDATE_RATE;
+
+const WIDTH: u16 = 600;
+const HEIGHT: u16 = 340;
+
+// tallied up over a run
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Stats {
+    pub longest: f32,
+    pub fastest: f32,
+    pub steps: u32,
+    pub fruit: u32
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats {
+            longest: 0.0,
+            fastest: 0.0,
+            steps: 0,
+            fruit: 0
+        }
+    }
+
+    // called every step of play
+    pub fn record(&mut self, speed: f32, length: f32) {
+        self.longest = self.longest.max(length);
+        self.fastest = self.fastest.max(speed);
+        self.steps += 1;
+    }
+
+    // e.g. "1:05"
+    pub fn duration(&self) -> String {
+        let seconds = self.steps / UPDATE_RATE;
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats::new()
+    }
+}
+
+// how a run ended, kept for the results screen and the score card
+pub struct Results {
+    pub graph: RunGraph,
+    pub stats: Stats,
+    pub score: i32,
+    pub length: f32,
+    pub mode: String,
+    pub seed: Option<u64>,
+    // how a two player game was decided
+    pub verdict: Option<String>
+}
+
+impl Results {
+    // draw the card off screen and write it to the user data directory,
+    // returning the path it was saved under
+    pub fn save_card(&self, ctx: &mut Context, screen: (f32, f32),
+                     top_speed: f32) -> GameResult<String> {
+        let canvas = graphics::Canvas::new(ctx, WIDTH, HEIGHT,
+                                           conf::NumSamples::One)?;
+        let coordinates = graphics::screen_coordinates(ctx);
+        graphics::set_canvas(ctx, Some(&canvas));
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(
+            0.0, 0.0, WIDTH as f32, HEIGHT as f32))?;
+        let drawn = self.draw_card(ctx, screen, top_speed);
+        graphics::set_canvas(ctx, None);
+        graphics::set_screen_coordinates(ctx, coordinates)?;
+        drawn?;
+
+        // canvases come back bottom row first
+        let rgba = canvas.image().to_rgba8(ctx)?;
+        let row = WIDTH as usize * 4;
+        let flipped: Vec<u8> = rgba.chunks(row).rev().flatten().cloned()
+            .collect();
+        let image = graphics::Image::from_rgba8(ctx, WIDTH, HEIGHT,
+                                                &flipped)?;
+        // named for when it was taken, counting up from there if there's
+        // already one from the same second
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut n = 0;
+        let mut path = format!("/scorecard-{}.png", secs);
+        while filesystem::exists(ctx, &path) {
+            n += 1;
+            path = format!("/scorecard-{}-{}.png", secs, n);
+        }
+        image.encode(ctx, graphics::ImageFormat::Png, &path)?;
+        Ok(path)
+    }
+
+    fn draw_card(&self, ctx: &mut Context, screen: (f32, f32),
+                 top_speed: f32) -> GameResult {
+        graphics::clear(ctx, (0.1, 0.2, 0.3, 1.0).into());
+        let seed = match self.seed {
+            Some(seed) => seed.to_string(),
+            None => "random".to_string()
+        };
+        let lines = [
+            (format!("score {}", self.score), 40.0),
+            (format!("length {:.0}   {}", self.length, self.mode), 22.0),
+            (format!("{}   {} fruit   top speed {:.1}",
+                     self.stats.duration(), self.stats.fruit,
+                     self.stats.fastest), 18.0),
+            (format!("seed {}", seed), 18.0),
+        ];
+        let mut y = 16.0;
+        for (line, size) in lines.iter() {
+            let text = graphics::Text::new(
+                (line.as_str(), graphics::Font::default(), *size));
+            graphics::draw(ctx, &text, graphics::DrawParam::new()
+                .dest(na::Point2::new(20.0, y)))?;
+            y += size + 10.0;
+        }
+
+        self.graph.draw(ctx, graphics::Rect::new(20.0, 150.0, 360.0, 170.0),
+                        top_speed)?;
+        let (w, h) = screen;
+        let map_h = (170.0 * h / w).min(170.0);
+        self.graph.draw_path(ctx,
+                             graphics::Rect::new(400.0, 150.0, 180.0, map_h),
+                             screen, top_speed)
+    }
+}
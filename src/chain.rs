@@ -0,0 +1,172 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::display::Display;
+use crate::fruit::{Fruit, FruitSprites};
+use crate::snake::Snake;
+use crate::UPDATE_RATE;
+
+// how many fruit make a chain and how long there is to eat them
+pub const LENGTH: usize = 5;
+pub const SECONDS: i32 = 15;
+// on top of the points for each fruit
+pub const BONUS: i32 = 250;
+
+pub enum Outcome {
+    // the right one, with more to go
+    Next,
+    Complete,
+    // one eaten out of turn
+    Broken
+}
+
+// numbered fruit that have to be eaten in order before time runs out
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Chain {
+    fruit: Vec<Fruit>,
+    // the number of the next one to eat, counting from 0
+    next: usize,
+    steps_left: i32
+}
+
+impl Chain {
+    pub fn new(fruit: Vec<Fruit>) -> Chain {
+        Chain {
+            fruit,
+            next: 0,
+            steps_left: SECONDS * UPDATE_RATE as i32
+        }
+    }
+
+    // false once time's up
+    pub fn update(&mut self) -> bool {
+        self.steps_left -= 1;
+        self.steps_left > 0
+    }
+
+    pub fn eat(&mut self, snake: &Snake) -> Option<Outcome> {
+        self.eat_touching(|f| snake.collide(&f.pos, f.radius))
+    }
+
+    // the first of the ones left that's `touching`, if any is
+    fn eat_touching(&mut self,
+                    touching: impl Fn(&Fruit) -> bool) -> Option<Outcome> {
+        let eaten = self.fruit.iter()
+            .enumerate()
+            .skip(self.next)
+            .find(|(_, f)| touching(f))
+            .map(|(i, _)| i)?;
+        if eaten != self.next {
+            return Some(Outcome::Broken);
+        }
+        self.next += 1;
+        if self.next == self.fruit.len() {
+            Some(Outcome::Complete)
+        } else {
+            Some(Outcome::Next)
+        }
+    }
+
+    // the ones still to eat, each with its number from `numbers` on top
+    pub fn draw(&self, ctx: &mut Context, display: &Display,
+                sprites: &mut FruitSprites,
+                numbers: &[graphics::Text]) -> GameResult {
+        let remaining = &self.fruit[self.next..];
+        sprites.draw(ctx, display, remaining)?;
+        for (i, f) in remaining.iter().enumerate() {
+            let text = &numbers[self.next + i];
+            let (tw, th) = text.dimensions(ctx);
+            let corner = f.pos
+                - na::Vector2::new(tw as f32 / 2.0, th as f32 / 2.0);
+            // the next one to eat stands out
+            let color = if i == 0 {
+                graphics::Color::new(1.0, 1.0, 0.3, 1.0)
+            } else {
+                graphics::WHITE
+            };
+            graphics::draw(ctx, text,
+                graphics::DrawParam::new().dest(corner).color(color))?;
+        }
+        Ok(())
+    }
+}
+
+// e.g. "chain 2/5 9s"
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "chain {}/{} {}s", self.next, self.fruit.len(),
+               self.steps_left / UPDATE_RATE as i32 + 1)
+    }
+}
+
+// 1 to LENGTH, made once to draw on every chain
+pub fn numbers() -> Vec<graphics::Text> {
+    (1..=LENGTH)
+        .map(|n| graphics::Text::new((n.to_string(),
+                                      graphics::Font::default(), 24.0)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fruit::Kind;
+
+    fn chain() -> Chain {
+        Chain::new((0..LENGTH)
+            .map(|i| Fruit {
+                pos: na::Point2::new(i as f32 * 100.0, 0.0),
+                radius: 10.0,
+                kind: Kind::Normal,
+                n: 0,
+                velocity: na::Vector2::new(0.0, 0.0)
+            })
+            .collect())
+    }
+
+    fn at(x: f32) -> impl Fn(&Fruit) -> bool {
+        move |f| f.pos.x == x
+    }
+
+    #[test]
+    fn eaten_in_order_completes() {
+        let mut chain = chain();
+        for i in 0..LENGTH - 1 {
+            assert!(matches!(chain.eat_touching(at(i as f32 * 100.0)),
+                             Some(Outcome::Next)));
+        }
+        assert!(matches!(
+            chain.eat_touching(at((LENGTH - 1) as f32 * 100.0)),
+            Some(Outcome::Complete)));
+    }
+
+    #[test]
+    fn eaten_out_of_turn_breaks() {
+        let mut chain = chain();
+        assert!(matches!(chain.eat_touching(at(200.0)),
+                         Some(Outcome::Broken)));
+    }
+
+    #[test]
+    fn eaten_ones_are_gone() {
+        let mut chain = chain();
+        assert!(chain.eat_touching(at(0.0)).is_some());
+        assert!(chain.eat_touching(at(0.0)).is_none());
+        assert!(chain.eat_touching(|_| false).is_none());
+        chain.update();
+        assert_eq!(chain.to_string(),
+                   format!("chain 1/{} {}s", LENGTH, SECONDS));
+    }
+
+    #[test]
+    fn runs_out_of_time() {
+        let mut chain = chain();
+        for _ in 1..SECONDS * UPDATE_RATE as i32 {
+            assert!(chain.update());
+        }
+        assert!(!chain.update());
+    }
+}
+
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+use crate::UPDATE_RATE;
+
+// how long after one fruit the next has to be eaten to build the combo,
+// and how high it goes
+const WINDOW: i32 = 3 * UPDATE_RATE as i32;
+const MAX: i32 = 8;
+
+// fruit eaten in quick succession multiply each other's points; when the
+// window runs out the multiplier drops back one at a time
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Combo {
+    pub multiplier: i32,
+    steps_left: i32
+}
+
+impl Combo {
+    pub fn new() -> Combo {
+        Combo {
+            multiplier: 1,
+            steps_left: 0
+        }
+    }
+
+    // the multiplier for a fruit eaten now
+    pub fn eat(&mut self) -> i32 {
+        if self.steps_left > 0 {
+            self.multiplier = (self.multiplier + 1).min(MAX);
+        }
+        self.steps_left = WINDOW;
+        self.multiplier
+    }
+
+    pub fn update(&mut self) {
+        if self.multiplier == 1 && self.steps_left == 0 {
+            return;
+        }
+        self.steps_left -= 1;
+        if self.steps_left <= 0 {
+            self.multiplier = (self.multiplier - 1).max(1);
+            self.steps_left = if self.multiplier > 1 { WINDOW } else { 0 };
+        }
+    }
+
+    // 1.0 just after a fruit down to 0.0 as the window closes
+    pub fn left(&self) -> f32 {
+        self.steps_left as f32 / WINDOW as f32
+    }
+}
+
+impl Default for Combo {
+    fn default() -> Combo {
+        Combo::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quick_fruit_build_up_to_the_max() {
+        let mut combo = Combo::new();
+        assert_eq!(combo.eat(), 1);
+        assert_eq!(combo.eat(), 2);
+        for _ in 0..MAX {
+            combo.eat();
+        }
+        assert_eq!(combo.multiplier, MAX);
+    }
+
+    #[test]
+    fn drops_back_one_window_at_a_time() {
+        let mut combo = Combo::new();
+        combo.eat();
+        combo.eat();
+        combo.eat();
+        for _ in 0..WINDOW {
+            combo.update();
+        }
+        assert_eq!(combo.multiplier, 2);
+        assert_eq!(combo.left(), 1.0);
+        for _ in 0..WINDOW {
+            combo.update();
+        }
+        assert_eq!(combo.multiplier, 1);
+        assert_eq!(combo.left(), 0.0);
+        // and a late one starts again from 1
+        assert_eq!(combo.eat(), 1);
+    }
+}
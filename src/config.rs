@@ -0,0 +1,176 @@
+use ggez::{filesystem, Context, GameError, GameResult};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path};
+
+use crate::display::Display;
+use crate::input::KeyBindings;
+use crate::net::NetConfig;
+use crate::rules::{Difficulty, Rules};
+
+// everything read from config.toml at startup; anything left out of the
+// file keeps its default
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub volume: f32,
+    // port for the telemetry websocket, if there is to be one
+    #[cfg(feature = "telemetry")]
+    pub telemetry: Option<u16>,
+    // let telemetry clients steer, only ever from the command line
+    #[cfg(feature = "telemetry")]
+    #[serde(skip)]
+    pub remote_control: bool,
+    pub rules: Rules,
+    pub display: Display,
+    pub keys: KeyBindings,
+    // bindings from config.toml that aren't one of the presets, so
+    // picking a preset on the settings screen doesn't lose them
+    #[serde(skip)]
+    pub custom_keys: Option<KeyBindings>,
+    pub net: NetConfig,
+    // what's been changed on the settings screen
+    #[serde(skip)]
+    pub settings: UserSettings,
+    // where those changes are saved to
+    #[serde(skip)]
+    pub settings_path: Option<path::PathBuf>
+}
+
+impl Config {
+    pub fn new() -> Config {
+        Config {
+            volume: 1.0,
+            #[cfg(feature = "telemetry")]
+            telemetry: None,
+            #[cfg(feature = "telemetry")]
+            remote_control: false,
+            rules: Rules::new(),
+            display: Display::new(),
+            keys: KeyBindings::new(),
+            custom_keys: None,
+            net: NetConfig::new(),
+            settings: UserSettings::default(),
+            settings_path: None
+        }
+    }
+
+    // a missing file just means the defaults
+    pub fn load(path: &path::Path) -> GameResult<Config> {
+        match read(path)? {
+            Some(text) => toml::from_str(&text)
+                .map_err(|e| config_error(path, e)),
+            None => Ok(Config::new())
+        }
+    }
+
+    // whatever was changed on the settings screen last time goes over
+    // what config.toml says
+    pub fn load_settings(&mut self, ctx: &Context) -> GameResult {
+        let path = filesystem::user_config_dir(ctx).join("settings.toml");
+        if let Some(text) = read(&path)? {
+            self.settings = toml::from_str(&text)
+                .map_err(|e| config_error(&path, e))?;
+        }
+        self.settings_path = Some(path);
+        if !self.keys.is_preset() {
+            self.custom_keys = Some(self.keys.clone());
+        }
+
+        let settings = &self.settings;
+        if let Some(volume) = settings.volume {
+            self.volume = volume;
+        }
+        if let Some(difficulty) = settings.difficulty {
+            self.rules.difficulty = difficulty;
+        }
+        if let Some(fullscreen) = settings.fullscreen {
+            self.display.fullscreen = fullscreen;
+        }
+        if let Some(scale) = settings.scale {
+            self.display.scale = scale;
+        }
+        if let Some(pixel_snap) = settings.pixel_snap {
+            self.display.pixel_snap = pixel_snap;
+        }
+        if let Some(speed_tint) = settings.speed_tint {
+            self.display.speed_tint = speed_tint;
+        }
+        if let Some(reduced_motion) = settings.reduced_motion {
+            self.display.reduced_motion = reduced_motion;
+        }
+        if let Some(attract) = settings.attract {
+            self.display.attract = attract;
+        }
+        if let Some(keys) = &settings.keys {
+            self.keys = keys.clone();
+        }
+        Ok(())
+    }
+
+    // only the settings screen's own changes are written, never
+    // config.toml or anything from the command line
+    pub fn save(&self) -> GameResult {
+        if let Some(path) = &self.settings_path {
+            let text = toml::to_string(&self.settings)
+                .map_err(|e| config_error(path, e))?;
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|e| config_error(dir, e))?;
+            }
+            fs::write(path, text).map_err(|e| config_error(path, e))?;
+        }
+        Ok(())
+    }
+
+    // command line flags win over the file
+    pub fn apply_args(&mut self, args: &[String]) {
+        self.rules.apply_args(args);
+        self.display.apply_args(args);
+        #[cfg(feature = "telemetry")]
+        {
+            for pair in args.windows(2) {
+                if pair[0] == "--telemetry" {
+                    if let Ok(port) = pair[1].parse::<u16>() {
+                        self.telemetry = Some(port);
+                    }
+                }
+            }
+            self.remote_control = args.iter().any(|a| a == "--remote-control");
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+// what's been changed on the settings screen, kept in the user's config
+// directory as settings.toml; anything never changed is left out so
+// config.toml still decides it
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UserSettings {
+    pub volume: Option<f32>,
+    pub difficulty: Option<Difficulty>,
+    pub fullscreen: Option<bool>,
+    pub scale: Option<u32>,
+    pub pixel_snap: Option<bool>,
+    pub speed_tint: Option<bool>,
+    pub reduced_motion: Option<bool>,
+    pub attract: Option<bool>,
+    // tables have to come after the plain values
+    pub keys: Option<KeyBindings>
+}
+
+fn read(path: &path::Path) -> GameResult<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(config_error(path, e))
+    }
+}
+
+fn config_error(path: &path::Path, e: impl std::fmt::Display) -> GameError {
+    GameError::ConfigError(format!("{}: {}", path.display(), e))
+}
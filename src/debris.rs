@@ -0,0 +1,60 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use std::collections::VecDeque;
+
+use crate::display::Display;
+use crate::segment::Segment;
+use crate::snake::Snake;
+use crate::{SEGMENT_SPACING, UPDATE_RATE};
+
+// a piece is left every this much of the cut off length
+const PIECE_EVERY: f32 = 20.0;
+const RADIUS: f32 = 6.0;
+pub const POINTS: i32 = 5;
+const SECONDS: i32 = 10;
+
+// what's left of a tail cut off in versus, for either snake to eat up
+// before it goes
+pub struct Debris {
+    pieces: Vec<na::Point2<f32>>,
+    steps_left: i32
+}
+
+impl Debris {
+    pub fn new(tail: VecDeque<Segment>) -> Debris {
+        let every = ((PIECE_EVERY / SEGMENT_SPACING) as usize).max(1);
+        Debris {
+            pieces: tail.iter().step_by(every).map(|s| s.pos).collect(),
+            steps_left: SECONDS * UPDATE_RATE as i32
+        }
+    }
+
+    // false once it's gone, eaten or not
+    pub fn update(&mut self) -> bool {
+        self.steps_left -= 1;
+        self.steps_left > 0 && !self.pieces.is_empty()
+    }
+
+    // how many pieces `snake` picked up
+    pub fn eat(&mut self, snake: &Snake) -> i32 {
+        let before = self.pieces.len();
+        self.pieces.retain(|p| !snake.collide(p, RADIUS));
+        (before - self.pieces.len()) as i32
+    }
+
+    // grey, fading out over the last second
+    pub fn draw(&self, ctx: &mut Context, display: &Display) -> GameResult {
+        if self.pieces.is_empty() {
+            return Ok(());
+        }
+        let fade = (self.steps_left as f32 / UPDATE_RATE as f32).min(1.0);
+        let color = graphics::Color::new(0.7, 0.7, 0.7, fade);
+        let mut builder = graphics::MeshBuilder::new();
+        for piece in self.pieces.iter() {
+            builder.circle(graphics::DrawMode::fill(), display.snap(*piece),
+                           RADIUS, 0.5, color);
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::new())
+    }
+}
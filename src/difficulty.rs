@@ -0,0 +1,77 @@
+// how the game tightens up as the snake grows, all as functions of the
+// length it's growing towards so they can be tuned in one place
+
+// the length at which everything is as tight as it gets
+pub const FULL_LENGTH: f32 = 3000.0;
+// the turning circle is this much wider at full length
+pub const TURN_PENALTY: f32 = 0.5;
+// fruit turn up at least this far from the head at full length, and
+// anywhere at the start
+pub const FRUIT_DISTANCE: f32 = 300.0;
+// this much of the neck stops being safe to touch at full length
+pub const GRACE_CUT: f32 = 0.5;
+
+// 0.0 at the start to 1.0 at FULL_LENGTH
+pub fn progress(length: f32) -> f32 {
+    (length / FULL_LENGTH).clamp(0.0, 1.0)
+}
+
+pub fn turn_radius(base: f32, length: f32) -> f32 {
+    base * (1.0 + TURN_PENALTY * progress(length))
+}
+
+pub fn fruit_distance(length: f32) -> f32 {
+    FRUIT_DISTANCE * progress(length)
+}
+
+// how many segments behind the head are never checked for a collision;
+// it never drops below `neck`, the segments the nose reaches even when
+// running straight
+pub fn grace(base: usize, length: f32, neck: usize) -> usize {
+    let cut = (base as f32 * (1.0 - GRACE_CUT * progress(length))).round();
+    (cut as usize).max(neck)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_clamped() {
+        assert_eq!(progress(-10.0), 0.0);
+        assert_eq!(progress(0.0), 0.0);
+        assert_eq!(progress(FULL_LENGTH / 2.0), 0.5);
+        assert_eq!(progress(FULL_LENGTH), 1.0);
+        assert_eq!(progress(FULL_LENGTH * 2.0), 1.0);
+    }
+
+    #[test]
+    fn turn_radius_widens_with_length() {
+        assert_eq!(turn_radius(100.0, 0.0), 100.0);
+        assert_eq!(turn_radius(100.0, FULL_LENGTH),
+                   100.0 * (1.0 + TURN_PENALTY));
+        assert!(turn_radius(100.0, 1000.0) < turn_radius(100.0, 2000.0));
+    }
+
+    #[test]
+    fn fruit_distance_grows_with_length() {
+        assert_eq!(fruit_distance(0.0), 0.0);
+        assert_eq!(fruit_distance(FULL_LENGTH), FRUIT_DISTANCE);
+        assert_eq!(fruit_distance(FULL_LENGTH * 3.0), FRUIT_DISTANCE);
+    }
+
+    #[test]
+    fn grace_shrinks_with_length() {
+        assert_eq!(grace(100, 0.0, 0), 100);
+        assert_eq!(grace(100, FULL_LENGTH, 0),
+                   (100.0 * (1.0 - GRACE_CUT)) as usize);
+        assert!(grace(100, 2000.0, 0) < grace(100, 1000.0, 0));
+    }
+
+    #[test]
+    fn grace_never_drops_below_the_neck() {
+        assert_eq!(grace(100, FULL_LENGTH, 80), 80);
+        assert_eq!(grace(100, 0.0, 80), 100);
+        assert_eq!(grace(10, 0.0, 80), 80);
+    }
+}
@@ -0,0 +1,93 @@
+use ggez::{conf, graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct Display {
+    // the playfield size, whatever size the window ends up
+    pub width: f32,
+    pub height: f32,
+    // integer multiple of the native resolution the window is drawn at
+    pub scale: u32,
+    // round sprite positions to whole pixels to stop sub-pixel shimmer
+    pub pixel_snap: bool,
+    // colour the body by the speed it was laid down at
+    pub speed_tint: bool,
+    pub fullscreen: bool,
+    // leave out purely decorative movement like the speed lines
+    pub reduced_motion: bool,
+    // computer snakes play each other behind the title screen
+    pub attract: bool
+}
+
+impl Display {
+    pub fn new() -> Display {
+        Display {
+            width: 800.0,
+            height: 600.0,
+            scale: 1,
+            pixel_snap: false,
+            speed_tint: false,
+            fullscreen: false,
+            reduced_motion: false,
+            attract: false
+        }
+    }
+
+    pub fn apply_args(&mut self, args: &[String]) {
+        for pair in args.windows(2) {
+            if pair[0] == "--scale" {
+                if let Ok(scale) = pair[1].parse::<u32>() {
+                    self.scale = scale;
+                }
+            }
+        }
+        self.scale = na::clamp(self.scale, 1, 3);
+        if args.iter().any(|a| a == "--pixel-snap") {
+            self.pixel_snap = true;
+        }
+        if args.iter().any(|a| a == "--speed-tint") {
+            self.speed_tint = true;
+        }
+    }
+
+    pub fn window_mode(&self) -> conf::WindowMode {
+        let scale = self.scale as f32;
+        conf::WindowMode::default()
+            .dimensions(self.width * scale, self.height * scale)
+            .fullscreen_type(self.fullscreen_type())
+    }
+
+    pub fn fullscreen_type(&self) -> conf::FullscreenType {
+        if self.fullscreen {
+            conf::FullscreenType::Desktop
+        } else {
+            conf::FullscreenType::Windowed
+        }
+    }
+
+    // must be applied before any images are loaded, as they pick up the
+    // default filter when they're created
+    pub fn apply(&self, ctx: &mut Context) -> GameResult {
+        if self.scale > 1 || self.pixel_snap {
+            graphics::set_default_filter(ctx, graphics::FilterMode::Nearest);
+        }
+        graphics::set_screen_coordinates(ctx,
+            graphics::Rect::new(0.0, 0.0, self.width, self.height))
+    }
+
+    pub fn snap(&self, p: na::Point2<f32>) -> na::Point2<f32> {
+        if self.pixel_snap {
+            na::Point2::new(p.x.round(), p.y.round())
+        } else {
+            p
+        }
+    }
+}
+
+impl Default for Display {
+    fn default() -> Display {
+        Display::new()
+    }
+}
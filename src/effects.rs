@@ -0,0 +1,269 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+
+use crate::{screen_size, UPDATE_RATE};
+
+// tints whatever it's applied to towards a colour for a moment, fading
+// back to normal
+pub struct Flash {
+    color: graphics::Color,
+    steps: u32,
+    left: u32
+}
+
+impl Flash {
+    pub fn new() -> Flash {
+        Flash {
+            color: graphics::WHITE,
+            steps: 1,
+            left: 0
+        }
+    }
+
+    pub fn trigger(&mut self, color: graphics::Color, seconds: f32) {
+        self.color = color;
+        self.steps = ((seconds * UPDATE_RATE as f32) as u32).max(1);
+        self.left = self.steps;
+    }
+
+    pub fn update(&mut self) {
+        self.left = self.left.saturating_sub(1);
+    }
+
+    // for DrawParam::color, blended over what it would have been
+    pub fn tint(&self, base: graphics::Color) -> graphics::Color {
+        let t = self.left as f32 / self.steps as f32;
+        graphics::Color::new(
+            base.r + (self.color.r - base.r) * t,
+            base.g + (self.color.g - base.g) * t,
+            base.b + (self.color.b - base.b) * t,
+            base.a)
+    }
+}
+
+impl Default for Flash {
+    fn default() -> Flash {
+        Flash::new()
+    }
+}
+
+// lines streaming in from the edges of the screen while the snake is
+// flat out
+pub struct SpeedLines {
+    // 0.0 hidden to 1.0 fully shown
+    alpha: f32,
+    angle: f32
+}
+
+impl SpeedLines {
+    pub fn new() -> SpeedLines {
+        SpeedLines {
+            alpha: 0.0,
+            angle: 0.0
+        }
+    }
+
+    // fades in or out over 0.3s
+    pub fn update(&mut self, flat_out: bool) {
+        let fade = 1.0 / (0.3 * UPDATE_RATE as f32);
+        let target = if flat_out { 1.0 } else { 0.0 };
+        self.alpha += na::clamp(target - self.alpha, -fade, fade);
+        self.angle += 0.2 / UPDATE_RATE as f32;
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+        if self.alpha <= 0.0 {
+            return Ok(());
+        }
+        let (w, h) = screen_size(ctx);
+        let centre = na::Point2::new(w / 2.0, h / 2.0);
+        let outer = (w * w + h * h).sqrt() / 2.0;
+        let color = graphics::Color::new(1.0, 1.0, 1.0, 0.4 * self.alpha);
+
+        let mut builder = graphics::MeshBuilder::new();
+        let n = 32;
+        for i in 0..n {
+            let a = self.angle +
+                i as f32 * std::f32::consts::PI * 2.0 / n as f32;
+            // alternate lengths so it doesn't look like a clock face
+            let inner = if i % 2 == 0 { 0.7 } else { 0.8 } * outer;
+            let dir = na::Vector2::new(a.cos(), a.sin());
+            builder.line(&[centre + dir * inner, centre + dir * outer],
+                         2.0, color)?;
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::new())
+    }
+}
+
+impl Default for SpeedLines {
+    fn default() -> SpeedLines {
+        SpeedLines::new()
+    }
+}
+
+// arms of dots spiralling in on a gravity well while it's pulling
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Swirl {
+    // steps left of pulling
+    left: u32,
+    angle: f32,
+    // whose snake the fruit's being pulled towards
+    player: usize
+}
+
+impl Swirl {
+    pub fn new() -> Swirl {
+        Swirl {
+            left: 0,
+            angle: 0.0,
+            player: 0
+        }
+    }
+
+    pub fn trigger(&mut self, seconds: f32, player: usize) {
+        self.left = (seconds * UPDATE_RATE as f32) as u32;
+        self.player = player;
+    }
+
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn active(&self) -> bool {
+        self.left > 0
+    }
+
+    pub fn update(&mut self) {
+        self.left = self.left.saturating_sub(1);
+        self.angle += 3.0 / UPDATE_RATE as f32;
+    }
+
+    // with reduced motion the arms hold still
+    pub fn draw(&self, ctx: &mut Context, centre: na::Point2<f32>,
+                reduced_motion: bool) -> GameResult {
+        if !self.active() {
+            return Ok(());
+        }
+        // fades out over the last half second
+        let fade = (self.left as f32 / (0.5 * UPDATE_RATE as f32)).min(1.0);
+        let spin = if reduced_motion { 0.0 } else { self.angle };
+
+        let mut builder = graphics::MeshBuilder::new();
+        for arm in 0..3 {
+            for i in 0..12 {
+                let t = i as f32 / 12.0;
+                let a = spin + arm as f32 * std::f32::consts::PI * 2.0 / 3.0
+                    + t * 4.0;
+                let r = 20.0 + 100.0 * t;
+                let color = graphics::Color::new(0.7, 0.5, 1.0,
+                                                 0.8 * (1.0 - t) * fade);
+                builder.circle(graphics::DrawMode::fill(),
+                               centre + na::Vector2::new(a.cos(), a.sin()) * r,
+                               4.0 - 2.0 * t, 0.5, color);
+            }
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::new())
+    }
+}
+
+impl Default for Swirl {
+    fn default() -> Swirl {
+        Swirl::new()
+    }
+}
+
+// text that's only laid out again when what it says changes, so drawing
+// the same thing every frame doesn't allocate
+pub struct Label {
+    line: String,
+    // what's being written this frame, swapped in if it's different
+    next: String,
+    size: f32,
+    text: graphics::Text
+}
+
+impl Label {
+    pub fn new(size: f32) -> Label {
+        Label {
+            line: String::new(),
+            next: String::new(),
+            size,
+            text: graphics::Text::new(("", graphics::Font::default(), size))
+        }
+    }
+
+    // starts this frame's line
+    pub fn clear(&mut self) {
+        self.next.clear();
+    }
+
+    pub fn push(&mut self, args: fmt::Arguments) {
+        // writing to a string can't fail
+        let _ = self.next.write_fmt(args);
+    }
+
+    pub fn text(&mut self) -> &graphics::Text {
+        if self.next != self.line {
+            std::mem::swap(&mut self.line, &mut self.next);
+            self.text = graphics::Text::new((
+                self.line.as_str(), graphics::Font::default(), self.size));
+        }
+        &self.text
+    }
+}
+
+// the points for a fruit drifting up from where it was eaten and fading;
+// the text's made once when it's added rather than every frame
+pub struct Popup {
+    multiplier: i32,
+    text: graphics::Text,
+    pos: na::Point2<f32>,
+    steps_left: u32
+}
+
+impl Popup {
+    // shown for a second
+    pub fn new(points: i32, multiplier: i32,
+               pos: na::Point2<f32>) -> Popup {
+        let line = if multiplier > 1 {
+            format!("+{} \u{d7} {}", points, multiplier)
+        } else {
+            format!("+{}", points)
+        };
+        Popup {
+            multiplier,
+            text: graphics::Text::new(
+                (line, graphics::Font::default(), 24.0)),
+            pos,
+            steps_left: UPDATE_RATE
+        }
+    }
+
+    // false once it's gone; with reduced motion it fades where it is
+    pub fn update(&mut self, reduced_motion: bool) -> bool {
+        if !reduced_motion {
+            self.pos.y -= 40.0 / UPDATE_RATE as f32;
+        }
+        self.steps_left = self.steps_left.saturating_sub(1);
+        self.steps_left > 0
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+        // gold while a combo's going
+        let mut color = if self.multiplier > 1 {
+            graphics::Color::new(1.0, 0.85, 0.3, 1.0)
+        } else {
+            graphics::WHITE
+        };
+        let (tw, th) = self.text.dimensions(ctx);
+        color.a *= self.steps_left as f32 / UPDATE_RATE as f32;
+        graphics::draw(ctx, &self.text, graphics::DrawParam::new()
+            .dest(self.pos - na::Vector2::new(tw as f32 / 2.0,
+                                              th as f32 / 2.0))
+            .color(color))
+    }
+}
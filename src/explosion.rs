@@ -0,0 +1,118 @@
+use ggez::{graphics, graphics::spritebatch, Context, GameResult};
+use ggez::nalgebra as na;
+use rand::{rngs::StdRng, Rng};
+
+use crate::audio::{Audio, Sound};
+use crate::display::Display;
+use crate::images::ImageCache;
+use crate::pool::Pool;
+use crate::segment::Segment;
+use crate::UPDATE_RATE;
+
+// the most pops going off at once, enough for a very long snake
+const MAX_POPS: usize = 512;
+
+struct Pop {
+    pos: na::Point2<f32>,
+    delay: i32
+}
+
+// made once and set off again each time a snake crashes
+pub struct Explosion {
+    batches: Vec<spritebatch::SpriteBatch>,
+    pops: Pool<Pop>,
+    step: i32
+}
+
+impl Explosion {
+    pub fn new(image_cache: &mut ImageCache,
+               ctx: &mut Context) -> GameResult<Explosion> {
+        let mut batches = Vec::<spritebatch::SpriteBatch>::new();
+        for i in 0..7 {
+            let s = format!("/pop0{}.png", i);
+            batches.push(spritebatch::SpriteBatch::new(
+                    image_cache.load(ctx, &s)?));
+        }
+
+        Ok(Explosion {
+            batches,
+            pops: Pool::new(MAX_POPS),
+            step: 0
+        })
+    }
+
+    // pops go off along the whole of the snake, or snakes, over the next
+    // second
+    pub fn start<'a>(&mut self, segments: impl Iterator<Item = &'a Segment>,
+                     rng: &mut StdRng) {
+        self.pops.clear();
+        self.step = 0;
+        for s in segments {
+            if rng.gen_range(0..10) < 1 {
+                self.pops.add(
+                    Pop {
+                        pos: na::Point2::new(
+                                s.pos.x + 20.0 * (
+                                     rng.gen::<f32>() - 0.5),
+                                s.pos.y + 20.0 * (
+                                     rng.gen::<f32>() - 0.5),
+                                 ),
+                        delay: rng.gen_range(0..UPDATE_RATE) as i32
+                    }
+                    );
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.pops.clear();
+    }
+
+    pub fn usage(&self) -> (usize, usize, usize) {
+        self.pops.usage()
+    }
+
+    pub fn update(&mut self, _ctx: &mut Context,
+                  audio: &mut Audio) -> GameResult {
+        if self.pops.is_empty() {
+            return Ok(());
+        }
+        // before moving on, so the ones with no delay are heard too
+        if self.pops.iter().any(|pop| pop.delay == self.step) {
+            audio.queue(Sound::Pop);
+        }
+        self.step += 1;
+        Ok(())
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, display: &Display) -> GameResult {
+        if self.pops.is_empty() {
+            return Ok(());
+        }
+        profile!("batching");
+        // the batches are kept between frames so drawing doesn't allocate
+        for batch in self.batches.iter_mut() {
+            batch.clear();
+        }
+
+        let n = self.batches.len() as i32;
+
+        for pop in self.pops.iter() {
+            // each pop plays through in a second
+            let frame = (self.step - pop.delay) * n / UPDATE_RATE as i32;
+            if frame >= 0 && frame < n {
+                self.batches[frame as usize].add(
+                    graphics::DrawParam::new()
+                    .offset(na::Point2::new(0.5, 0.5))
+                    .dest(display.snap(pop.pos))
+                );
+            }
+        }
+
+        for batch in self.batches.iter() {
+            graphics::draw(ctx, batch, graphics::DrawParam::new())?;
+        }
+
+        Ok(())
+    }
+}
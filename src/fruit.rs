@@ -0,0 +1,180 @@
+use ggez::{graphics, graphics::spritebatch, Context, GameResult};
+use ggez::nalgebra as na;
+use rand::{rngs::StdRng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::display::Display;
+use crate::images::ImageCache;
+
+// what eating it does
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum Kind {
+    // grow
+    Normal,
+    // grow and score extra
+    Bonus,
+    // lose some length
+    Shrink,
+    // a few seconds with a higher top speed
+    SpeedBoost,
+    // runs away from the snake, grow and score a lot extra
+    Skittish,
+    // pulls all the fruit in towards the snake for a few seconds
+    Gravity,
+    // the end of the run
+    Poison
+}
+
+impl Kind {
+    // mostly normal fruit, with speed boosts the rarest, and poison
+    // creeping in as the score goes up
+    fn random(rng: &mut StdRng, score: i32) -> Kind {
+        let poison = (2 + score / 100).min(20) as f32 / 100.0;
+        if rng.gen::<f32>() < poison {
+            return Kind::Poison;
+        }
+        match rng.gen_range(0..100) {
+            0..=64 => Kind::Normal,
+            65..=69 => Kind::Skittish,
+            70..=84 => Kind::Bonus,
+            85..=91 => Kind::Shrink,
+            92..=94 => Kind::Gravity,
+            _ => Kind::SpeedBoost
+        }
+    }
+
+    // normal fruit share the first two images, the rest get one each
+    fn image(self, rng: &mut StdRng) -> usize {
+        match self {
+            Kind::Normal | Kind::Poison => rng.gen_range(0..2),
+            Kind::Skittish => 1,
+            Kind::Bonus | Kind::Gravity => 2,
+            Kind::Shrink => 3,
+            Kind::SpeedBoost => 4
+        }
+    }
+}
+
+// how close a snake's head gets before skittish fruit run, and how fast
+// they go, in pixels a second; well under what a snake can manage
+const FLEE_DISTANCE: f32 = 200.0;
+const FLEE_SPEED: f32 = 90.0;
+// how fast a gravity well pulls fruit in
+const PULL_SPEED: f32 = 240.0;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Fruit {
+    pub pos: na::Point2<f32>,
+    pub radius: f32,
+    pub kind: Kind,
+    // which of the fruit images it is
+    pub n: usize,
+    // in pixels a second, only skittish fruit move on their own
+    pub velocity: na::Vector2<f32>
+}
+
+impl Fruit {
+    pub fn new(sprites: &FruitSprites,
+               rng: &mut StdRng,
+               w: f32, h: f32, score: i32) -> Fruit {
+        let kind = Kind::random(rng, score);
+        Fruit {
+            pos: na::Point2::new(
+                     rng.gen::<f32>() * w,
+                     rng.gen::<f32>() * h
+                     ),
+            radius: sprites.radius,
+            kind,
+            n: kind.image(rng),
+            velocity: na::Vector2::new(0.0, 0.0)
+        }
+    }
+
+    // everything heads for `pull` while a gravity well's on; otherwise
+    // skittish fruit head away from the nearest of `heads` when it gets
+    // close, and everything comes to a stop; fruit stays on the screen
+    // rather than wrapping
+    pub fn update(&mut self, heads: &[na::Point2<f32>],
+                  pull: Option<na::Point2<f32>>, w: f32, h: f32, dt: f32) {
+        let still = na::Vector2::new(0.0, 0.0);
+        if pull.is_none() && self.kind != Kind::Skittish &&
+                self.velocity == still {
+            return;
+        }
+        let nearest = heads.iter()
+            .map(|head| self.pos - head)
+            .min_by(|a, b| a.norm().partial_cmp(&b.norm()).unwrap());
+        let wanted = match (pull, nearest) {
+            (Some(pull), _) if pull != self.pos =>
+                (pull - self.pos).normalize() * PULL_SPEED,
+            (None, Some(away)) if self.kind == Kind::Skittish &&
+                    away.norm() > 0.0 && away.norm() < FLEE_DISTANCE =>
+                away.normalize() * FLEE_SPEED,
+            _ => still
+        };
+        // about a fifth of a second to get going or stop
+        self.velocity += (wanted - self.velocity) * (5.0 * dt).min(1.0);
+        self.pos += self.velocity * dt;
+        self.pos.x = na::clamp(self.pos.x, self.radius, w - self.radius);
+        self.pos.y = na::clamp(self.pos.y, self.radius, h - self.radius);
+    }
+}
+
+// one batch per kind of fruit, however many of each there are
+pub struct FruitSprites {
+    batches: Vec<spritebatch::SpriteBatch>,
+    radius: f32
+}
+
+impl FruitSprites {
+    pub fn new(
+        image_cache: &mut ImageCache,
+        ctx: &mut Context) -> GameResult<FruitSprites> {
+        let mut batches = Vec::<spritebatch::SpriteBatch>::new();
+        let mut radius = 0.0;
+        for i in 0..=4 {
+            let s = format!("/fruit{}0.png", i);
+            let image = image_cache.load(ctx, &s)?;
+            if batches.is_empty() {
+                radius = (image.height() as f32) / 2.0;
+            }
+            batches.push(spritebatch::SpriteBatch::new(image));
+        }
+
+        Ok(FruitSprites {
+            batches,
+            radius
+        })
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, display: &Display,
+                fruit: &[Fruit]) -> GameResult {
+        for batch in self.batches.iter_mut() {
+            batch.clear();
+        }
+        for f in fruit {
+            // poison looks like a normal fruit gone bad, and skittish ones
+            // have gone pale
+            let color = match f.kind {
+                Kind::Poison => graphics::Color::new(0.4, 0.9, 0.3, 1.0),
+                Kind::Skittish => graphics::Color::new(0.6, 0.8, 1.0, 1.0),
+                Kind::Gravity => graphics::Color::new(0.7, 0.5, 1.0, 1.0),
+                _ => graphics::WHITE
+            };
+            self.batches[f.n].add(
+                graphics::DrawParam::new()
+                    .offset(na::Point2::new(0.5, 0.5))
+                    .dest(display.snap(f.pos))
+                    .color(color)
+            );
+        }
+        for batch in self.batches.iter() {
+            graphics::draw(ctx, batch, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+}
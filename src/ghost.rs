@@ -0,0 +1,124 @@
+use ggez::{filesystem, Context, GameError, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::{fs, path};
+
+use crate::display::Display;
+use crate::images::ImageCache;
+use crate::rules::{Difficulty, Rules};
+use crate::snake::Snake;
+
+// how solid the ghost is drawn
+const OPACITY: f32 = 0.3;
+
+// where the head was every step of a run, and how long the snake
+// wanted to be
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Replay {
+    pub score: i32,
+    steps: Vec<(na::Point2<f32>, f32, f32)>
+}
+
+impl Replay {
+    pub fn new() -> Replay {
+        Replay {
+            score: 0,
+            steps: Vec::<(na::Point2<f32>, f32, f32)>::new()
+        }
+    }
+
+    // called every step of play
+    pub fn record(&mut self, snake: &Snake) {
+        self.steps.push((snake.head.pos, snake.head.angle,
+                         snake.desired_length()));
+    }
+
+    // the best run is kept for each difficulty, they turn differently
+    pub fn write(&self, ctx: &Context, difficulty: Difficulty) -> GameResult {
+        let path = replay_path(ctx, difficulty);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string(self)
+            .map_err(|e| GameError::ResourceLoadError(e.to_string()))?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn read(ctx: &Context, difficulty: Difficulty) -> GameResult<Replay> {
+        let text = fs::read_to_string(replay_path(ctx, difficulty))?;
+        serde_json::from_str(&text)
+            .map_err(|e| GameError::ResourceLoadError(e.to_string()))
+    }
+}
+
+impl Default for Replay {
+    fn default() -> Replay {
+        Replay::new()
+    }
+}
+
+fn replay_path(ctx: &Context, difficulty: Difficulty) -> path::PathBuf {
+    filesystem::user_data_dir(ctx)
+        .join(format!("best-{}.json", difficulty.name().to_lowercase()))
+}
+
+// a see-through snake going round exactly as the best run did
+pub struct Ghost {
+    replay: Replay,
+    snake: Snake,
+    // the next step of the replay
+    step: usize
+}
+
+impl Ghost {
+    pub fn new(image_cache: &mut ImageCache, ctx: &mut Context,
+               rules: &Rules, replay: Replay) -> GameResult<Ghost> {
+        let mut snake = Snake::new(image_cache, ctx, rules)?;
+        snake.opacity = OPACITY;
+        Ok(Ghost {
+            replay,
+            snake,
+            step: 0
+        })
+    }
+
+    // the score to beat
+    pub fn score(&self) -> i32 {
+        self.replay.score
+    }
+
+    // once the replay's over the ghost is gone
+    pub fn finished(&self) -> bool {
+        self.step >= self.replay.steps.len()
+    }
+
+    // how far through the replay it is
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    // caught up to `step` of the replay, as after a quick load
+    pub fn skip_to(&mut self, screen: (f32, f32), step: usize) {
+        while self.step < step && !self.finished() {
+            self.update(screen);
+        }
+    }
+
+    pub fn update(&mut self, screen: (f32, f32)) {
+        if let Some(&(pos, angle, length)) = self.replay.steps.get(self.step) {
+            self.snake.follow(screen, pos, angle, length);
+            self.step += 1;
+        }
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, display: &Display,
+                alpha: f32) -> GameResult {
+        if self.finished() {
+            return Ok(());
+        }
+        // looking where it's going
+        let look_at = self.snake.head.pos + self.snake.head.heading() * 100.0;
+        self.snake.draw(ctx, display, look_at, alpha)
+    }
+}
@@ -0,0 +1,134 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::snake::speed_color;
+use crate::UPDATE_RATE;
+
+// past this many samples every other one is dropped and they're taken
+// half as often, so a long run still fits
+const MAX_SAMPLES: usize = 400;
+
+// speed and length over a run, for the results screen
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RunGraph {
+    // (speed, length, head position)
+    samples: Vec<(f32, f32, na::Point2<f32>)>,
+    // steps between samples
+    interval: i32,
+    countdown: i32
+}
+
+impl RunGraph {
+    pub fn new() -> RunGraph {
+        RunGraph {
+            samples: Vec::<(f32, f32, na::Point2<f32>)>::new(),
+            interval: UPDATE_RATE as i32 / 4,
+            countdown: 0
+        }
+    }
+
+    // called every step
+    pub fn record(&mut self, speed: f32, length: f32,
+                  pos: na::Point2<f32>) {
+        self.countdown -= 1;
+        if self.countdown > 0 {
+            return;
+        }
+        self.countdown = self.interval;
+        self.samples.push((speed, length, pos));
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples = self.samples.iter().step_by(2).cloned().collect();
+            self.interval *= 2;
+        }
+    }
+
+    // the last sample is wherever the run ended, not just the last
+    // one that happened to be taken
+    pub fn finish(&mut self, speed: f32, length: f32,
+                  pos: na::Point2<f32>) {
+        self.samples.push((speed, length, pos));
+    }
+
+    // seconds of run between each sample
+    pub fn seconds(&self) -> f32 {
+        self.samples.len() as f32 * self.interval as f32 / UPDATE_RATE as f32
+    }
+
+    // speed against `top_speed` and length against the longest it got,
+    // both filling `rect`
+    pub fn draw(&self, ctx: &mut Context, rect: graphics::Rect,
+                top_speed: f32) -> GameResult {
+        let back = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::fill(), rect,
+            graphics::Color::new(0.0, 0.0, 0.0, 0.5))?;
+        graphics::draw(ctx, &back, graphics::DrawParam::new())?;
+
+        let longest = self.samples.iter().map(|s| s.1).fold(1.0, f32::max);
+        let speed = graphics::Color::new(1.0, 0.6, 0.2, 1.0);
+        let length = graphics::Color::new(0.3, 0.9, 0.4, 1.0);
+        self.draw_line(ctx, rect, |s| s.0 / top_speed, speed)?;
+        self.draw_line(ctx, rect, |s| s.1 / longest, length)?;
+
+        let key = graphics::Text::new(
+            (format!("speed / length over {:.0}s", self.seconds()),
+             graphics::Font::default(), 16.0));
+        graphics::draw(ctx, &key, graphics::DrawParam::new()
+            .dest(na::Point2::new(rect.x + 6.0, rect.y + 4.0)))
+    }
+
+    fn draw_line(&self, ctx: &mut Context, rect: graphics::Rect,
+                 value: impl Fn(&(f32, f32, na::Point2<f32>)) -> f32,
+                 color: graphics::Color) -> GameResult {
+        // a line needs two points
+        if self.samples.len() < 2 {
+            return Ok(());
+        }
+        let last = (self.samples.len() - 1) as f32;
+        let points: Vec<na::Point2<f32>> = self.samples.iter()
+            .enumerate()
+            .map(|(i, s)| na::Point2::new(
+                rect.x + rect.w * i as f32 / last,
+                rect.y + rect.h * (1.0 - na::clamp(value(s), 0.0, 1.0))))
+            .collect();
+        let line = graphics::Mesh::new_line(ctx, &points, 2.0, color)?;
+        graphics::draw(ctx, &line, graphics::DrawParam::new())
+    }
+
+    // where the head went, shrunk from a `screen` sized playfield into
+    // `rect` and coloured by speed like the snake, with a red dot where
+    // it ended; the trace is broken wherever it wrapped
+    pub fn draw_path(&self, ctx: &mut Context, rect: graphics::Rect,
+                     screen: (f32, f32), top_speed: f32) -> GameResult {
+        let (w, h) = screen;
+        let to_map = |pos: &na::Point2<f32>| na::Point2::new(
+            rect.x + rect.w * pos.x / w, rect.y + rect.h * pos.y / h);
+
+        let mut mesh = graphics::MeshBuilder::new();
+        mesh.rectangle(graphics::DrawMode::fill(), rect,
+                       graphics::Color::new(0.0, 0.0, 0.0, 0.5));
+        mesh.rectangle(graphics::DrawMode::stroke(1.0), rect,
+                       graphics::WHITE);
+        for pair in self.samples.windows(2) {
+            let (a, b) = (pair[0].2, pair[1].2);
+            if (a.x - b.x).abs() > w / 2.0 || (a.y - b.y).abs() > h / 2.0 ||
+                    a == b {
+                continue;
+            }
+            mesh.line(&[to_map(&a), to_map(&b)], 1.5,
+                      speed_color(pair[1].0, top_speed))?;
+        }
+        if let Some((_, _, end)) = self.samples.last() {
+            mesh.circle(graphics::DrawMode::fill(), to_map(end), 3.0, 0.5,
+                        graphics::Color::new(1.0, 0.2, 0.2, 1.0));
+        }
+        let mesh = mesh.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::new())
+    }
+}
+
+impl Default for RunGraph {
+    fn default() -> RunGraph {
+        RunGraph::new()
+    }
+}
@@ -0,0 +1,151 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use rand::{rngs::StdRng, Rng};
+
+use crate::UPDATE_RATE;
+
+const RADIUS: f32 = 90.0;
+// pixels a second it drifts
+const DRIFT: f32 = 20.0;
+// how long before it picks somewhere else
+const MOVE_EVERY: i32 = 20 * UPDATE_RATE as i32;
+pub const POINTS_PER_SECOND: i32 = 10;
+
+// a slowly drifting circle worth points to whichever player has their
+// head in it alone
+pub struct Hill {
+    centre: na::Point2<f32>,
+    velocity: na::Vector2<f32>,
+    steps_to_move: i32,
+    // steps each player has held it since they last scored
+    held: [i32; 2],
+    // who's in it right now
+    inside: [bool; 2]
+}
+
+impl Hill {
+    pub fn new(rng: &mut StdRng, w: f32, h: f32) -> Hill {
+        let mut hill = Hill {
+            centre: na::Point2::new(w / 2.0, h / 2.0),
+            velocity: na::Vector2::new(0.0, 0.0),
+            steps_to_move: 0,
+            held: [0; 2],
+            inside: [false; 2]
+        };
+        hill.reposition(rng, w, h);
+        hill
+    }
+
+    fn reposition(&mut self, rng: &mut StdRng, w: f32, h: f32) {
+        self.centre = na::Point2::new(rng.gen_range(RADIUS..w - RADIUS),
+                                      rng.gen_range(RADIUS..h - RADIUS));
+        let a = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+        self.velocity = na::Vector2::new(a.cos(), a.sin()) * DRIFT;
+        self.steps_to_move = MOVE_EVERY;
+    }
+
+    // drifts, bouncing off the edges, and returns the points each
+    // player's earned this step; nobody scores while it's contested
+    pub fn update(&mut self, rng: &mut StdRng, w: f32, h: f32,
+                  heads: [na::Point2<f32>; 2], dt: f32) -> [i32; 2] {
+        self.steps_to_move -= 1;
+        if self.steps_to_move <= 0 {
+            self.reposition(rng, w, h);
+        }
+        self.centre += self.velocity * dt;
+        if self.centre.x < RADIUS || self.centre.x > w - RADIUS {
+            self.velocity.x = -self.velocity.x;
+        }
+        if self.centre.y < RADIUS || self.centre.y > h - RADIUS {
+            self.velocity.y = -self.velocity.y;
+        }
+        self.centre.x = na::clamp(self.centre.x, RADIUS, w - RADIUS);
+        self.centre.y = na::clamp(self.centre.y, RADIUS, h - RADIUS);
+
+        for (inside, head) in self.inside.iter_mut().zip(heads.iter()) {
+            *inside = na::distance(&self.centre, head) < RADIUS;
+        }
+        let mut points = [0; 2];
+        let holder = match self.inside {
+            [true, false] => 0,
+            [false, true] => 1,
+            _ => return points
+        };
+        self.held[holder] += 1;
+        if self.held[holder] >= UPDATE_RATE as i32 {
+            self.held[holder] = 0;
+            points[holder] = POINTS_PER_SECOND;
+        }
+        points
+    }
+
+    // white when empty, gold when held and red when contested; a ring
+    // shrinks in towards the middle as the move gets near
+    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+        let color = match self.inside {
+            [true, true] => graphics::Color::new(1.0, 0.3, 0.3, 1.0),
+            [false, false] => graphics::WHITE,
+            _ => graphics::Color::new(1.0, 0.85, 0.3, 1.0)
+        };
+        let mut fill = color;
+        fill.a = 0.15;
+        let left = self.steps_to_move as f32 / MOVE_EVERY as f32;
+        let mut builder = graphics::MeshBuilder::new();
+        builder.circle(graphics::DrawMode::fill(), self.centre, RADIUS,
+                       1.0, fill);
+        builder.circle(graphics::DrawMode::stroke(3.0), self.centre, RADIUS,
+                       1.0, color);
+        builder.circle(graphics::DrawMode::stroke(1.0), self.centre,
+                       (RADIUS * left).max(1.0), 1.0, color);
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    const DT: f32 = 1.0 / UPDATE_RATE as f32;
+
+    fn hill() -> (Hill, StdRng) {
+        let mut rng = StdRng::seed_from_u64(1);
+        (Hill::new(&mut rng, 800.0, 600.0), rng)
+    }
+
+    #[test]
+    fn held_alone_scores_each_second() {
+        let (mut hill, mut rng) = hill();
+        let away = na::Point2::new(-1000.0, -1000.0);
+        let mut total = [0; 2];
+        for _ in 0..UPDATE_RATE * 2 {
+            let points = hill.update(&mut rng, 800.0, 600.0,
+                                     [away, hill.centre], DT);
+            total[0] += points[0];
+            total[1] += points[1];
+        }
+        assert_eq!(total, [0, 2 * POINTS_PER_SECOND]);
+    }
+
+    #[test]
+    fn contested_scores_nothing() {
+        let (mut hill, mut rng) = hill();
+        for _ in 0..UPDATE_RATE * 2 {
+            let centre = hill.centre;
+            assert_eq!(hill.update(&mut rng, 800.0, 600.0,
+                                   [centre, centre], DT), [0, 0]);
+        }
+    }
+
+    #[test]
+    fn stays_on_screen() {
+        let (mut hill, mut rng) = hill();
+        let away = na::Point2::new(-1000.0, -1000.0);
+        for _ in 0..MOVE_EVERY * 3 {
+            hill.update(&mut rng, 800.0, 600.0, [away, away], DT);
+            assert!(hill.centre.x >= RADIUS && hill.centre.x <= 800.0 - RADIUS);
+            assert!(hill.centre.y >= RADIUS && hill.centre.y <= 600.0 - RADIUS);
+        }
+    }
+}
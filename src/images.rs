@@ -0,0 +1,69 @@
+use ggez::{graphics, Context, GameResult};
+use std::collections::{HashMap, HashSet};
+
+pub struct ImageCache {
+    hashmap: HashMap<String, graphics::Image>,
+    // images known to be broken, which get a checkerboard instead as
+    // would any that fail to load
+    placeholders: HashSet<String>
+}
+
+impl ImageCache {
+    pub fn new() -> ImageCache {
+        ImageCache {
+            hashmap: HashMap::<String, graphics::Image>::new(),
+            placeholders: HashSet::<String>::new()
+        }
+    }
+
+    pub fn use_placeholder(&mut self, path: &str) {
+        self.placeholders.insert(path.to_string());
+    }
+    
+    pub fn load(&mut self,
+                ctx: &mut Context, path: &str) -> GameResult<graphics::Image> {
+        match self.hashmap.get(path) {
+            Some(image) => {
+                Ok(image.clone())
+            }
+            None => {
+                let image = if self.placeholders.contains(path) {
+                    placeholder(ctx)?
+                } else {
+                    match graphics::Image::new(ctx, path) {
+                        Ok(image) => image,
+                        Err(e) => {
+                            crate::warn(format_args!(
+                                "using a placeholder for {}: {}", path, e));
+                            placeholder(ctx)?
+                        }
+                    }
+                };
+                self.hashmap.insert(path.to_string(), image.clone());
+                Ok(image)
+            }
+        }
+    }
+}
+
+impl Default for ImageCache {
+    fn default() -> ImageCache {
+        ImageCache::new()
+    }
+}
+
+// magenta and black squares, hard to mistake for real art
+fn placeholder(ctx: &mut Context) -> GameResult<graphics::Image> {
+    let size = 64;
+    let mut rgba = Vec::<u8>::new();
+    for y in 0..size {
+        for x in 0..size {
+            if (x / 8 + y / 8) % 2 == 0 {
+                rgba.extend_from_slice(&[255, 0, 255, 255]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 255]);
+            }
+        }
+    }
+    graphics::Image::from_rgba8(ctx, size, size, &rgba)
+}
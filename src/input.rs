@@ -0,0 +1,364 @@
+use ggez::{event, graphics, input::keyboard, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+use crate::{collide, screen_size};
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Left,
+    Right,
+    Straight
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Speed {
+    Accelerate,
+    Brake,
+    Coast
+}
+
+// what a key or button press means to the game, whatever it came from
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Steer(Direction),
+    Throttle(Speed),
+    Pause,
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Settings,
+    QuickSave,
+    QuickLoad,
+    Share,
+    Quit
+}
+
+// what one player is holding down
+#[derive(Clone, Copy)]
+pub struct Held {
+    pub direction: Direction,
+    pub accelerate: Speed
+}
+
+impl Held {
+    pub fn new() -> Held {
+        Held {
+            direction: Direction::Straight,
+            accelerate: Speed::Coast
+        }
+    }
+}
+
+impl Default for Held {
+    fn default() -> Held {
+        Held::new()
+    }
+}
+
+// how far the stick has to move before it counts as steering
+pub const STICK_DEAD_ZONE: f32 = 0.5;
+
+// the keys that can be rebound; menus always answer to the arrow keys,
+// Space/Return, Tab, F5/F9, F12 and Escape as well
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "KeyNames", into = "KeyNames")]
+pub struct KeyBindings {
+    pub left: keyboard::KeyCode,
+    pub right: keyboard::KeyCode,
+    pub accelerate: keyboard::KeyCode,
+    pub brake: keyboard::KeyCode,
+    pub pause: keyboard::KeyCode
+}
+
+impl KeyBindings {
+    pub fn new() -> KeyBindings {
+        KeyBindings {
+            left: keyboard::KeyCode::A,
+            right: keyboard::KeyCode::D,
+            accelerate: keyboard::KeyCode::W,
+            brake: keyboard::KeyCode::S,
+            pause: keyboard::KeyCode::P
+        }
+    }
+
+    pub fn arrows() -> KeyBindings {
+        KeyBindings {
+            left: keyboard::KeyCode::Left,
+            right: keyboard::KeyCode::Right,
+            accelerate: keyboard::KeyCode::Up,
+            brake: keyboard::KeyCode::Down,
+            pause: keyboard::KeyCode::P
+        }
+    }
+
+    pub fn is_preset(&self) -> bool {
+        *self == KeyBindings::new() || *self == KeyBindings::arrows()
+    }
+
+    // whether any of the steering or throttle keys are shared
+    pub fn clashes(&self, other: &KeyBindings) -> bool {
+        let driving = |k: &KeyBindings| [k.left, k.right, k.accelerate,
+                                          k.brake];
+        driving(self).iter().any(|k| driving(other).contains(k))
+    }
+
+    pub fn action(&self, keycode: keyboard::KeyCode) -> Option<Action> {
+        match keycode {
+            k if k == self.left => Some(Action::Steer(Direction::Left)),
+            k if k == self.right => Some(Action::Steer(Direction::Right)),
+            k if k == self.accelerate =>
+                Some(Action::Throttle(Speed::Accelerate)),
+            k if k == self.brake => Some(Action::Throttle(Speed::Brake)),
+            k if k == self.pause => Some(Action::Pause),
+            keyboard::KeyCode::Up => Some(Action::Up),
+            keyboard::KeyCode::Down => Some(Action::Down),
+            keyboard::KeyCode::Left => Some(Action::Left),
+            keyboard::KeyCode::Right => Some(Action::Right),
+            keyboard::KeyCode::Space | keyboard::KeyCode::Return =>
+                Some(Action::Confirm),
+            keyboard::KeyCode::Tab => Some(Action::Settings),
+            keyboard::KeyCode::F5 => Some(Action::QuickSave),
+            keyboard::KeyCode::F9 => Some(Action::QuickLoad),
+            keyboard::KeyCode::F12 => Some(Action::Share),
+            keyboard::KeyCode::Escape => Some(Action::Quit),
+            _ => None
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        KeyBindings::new()
+    }
+}
+
+// key bindings as they're written in the config file
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+struct KeyNames {
+    left: String,
+    right: String,
+    accelerate: String,
+    brake: String,
+    pause: String
+}
+
+impl Default for KeyNames {
+    fn default() -> KeyNames {
+        KeyNames {
+            left: "A".to_string(),
+            right: "D".to_string(),
+            accelerate: "W".to_string(),
+            brake: "S".to_string(),
+            pause: "P".to_string()
+        }
+    }
+}
+
+impl TryFrom<KeyNames> for KeyBindings {
+    type Error = String;
+
+    fn try_from(names: KeyNames) -> Result<KeyBindings, String> {
+        let key = |name: &str| key_from_name(name)
+            .ok_or(format!("unknown key \"{}\"", name));
+        Ok(KeyBindings {
+            left: key(&names.left)?,
+            right: key(&names.right)?,
+            accelerate: key(&names.accelerate)?,
+            brake: key(&names.brake)?,
+            pause: key(&names.pause)?
+        })
+    }
+}
+
+impl From<KeyBindings> for KeyNames {
+    fn from(keys: KeyBindings) -> KeyNames {
+        KeyNames {
+            left: key_name(keys.left).to_string(),
+            right: key_name(keys.right).to_string(),
+            accelerate: key_name(keys.accelerate).to_string(),
+            brake: key_name(keys.brake).to_string(),
+            pause: key_name(keys.pause).to_string()
+        }
+    }
+}
+
+const KEY_NAMES: [(&str, keyboard::KeyCode); 50] = {
+    use keyboard::KeyCode::*;
+    [
+        ("A", A), ("B", B), ("C", C), ("D", D), ("E", E), ("F", F),
+        ("G", G), ("H", H), ("I", I), ("J", J), ("K", K), ("L", L),
+        ("M", M), ("N", N), ("O", O), ("P", P), ("Q", Q), ("R", R),
+        ("S", S), ("T", T), ("U", U), ("V", V), ("W", W), ("X", X),
+        ("Y", Y), ("Z", Z),
+        ("0", Key0), ("1", Key1), ("2", Key2), ("3", Key3), ("4", Key4),
+        ("5", Key5), ("6", Key6), ("7", Key7), ("8", Key8), ("9", Key9),
+        ("Up", Up), ("Down", Down), ("Left", Left), ("Right", Right),
+        ("Space", Space), ("Return", Return),
+        ("LShift", LShift), ("RShift", RShift),
+        ("LControl", LControl), ("RControl", RControl),
+        ("Comma", Comma), ("Period", Period), ("Slash", Slash),
+        ("Semicolon", Semicolon),
+    ]
+};
+
+pub fn key_from_name(name: &str) -> Option<keyboard::KeyCode> {
+    KEY_NAMES.iter().find(|(n, _)| *n == name).map(|(_, k)| *k)
+}
+
+// every bindable key has a name, anything else was never bound
+pub fn key_name(keycode: keyboard::KeyCode) -> &'static str {
+    KEY_NAMES.iter().find(|(_, k)| *k == keycode).map_or("?", |(n, _)| *n)
+}
+
+pub fn button_action(button: event::Button) -> Option<Action> {
+    match button {
+        event::Button::DPadLeft => Some(Action::Steer(Direction::Left)),
+        event::Button::DPadRight => Some(Action::Steer(Direction::Right)),
+        event::Button::RightTrigger | event::Button::RightTrigger2 =>
+            Some(Action::Throttle(Speed::Accelerate)),
+        event::Button::LeftTrigger | event::Button::LeftTrigger2 =>
+            Some(Action::Throttle(Speed::Brake)),
+        event::Button::Start => Some(Action::Pause),
+        event::Button::Select => Some(Action::Settings),
+        event::Button::DPadUp => Some(Action::Up),
+        event::Button::DPadDown => Some(Action::Down),
+        event::Button::South => Some(Action::Confirm),
+        _ => None
+    }
+}
+
+pub fn stick_direction(x: f32) -> Direction {
+    if x < -STICK_DEAD_ZONE {
+        Direction::Left
+    } else if x > STICK_DEAD_ZONE {
+        Direction::Right
+    } else {
+        Direction::Straight
+    }
+}
+
+struct TouchButton {
+    centre: na::Point2<f32>,
+    action: Action,
+    label: &'static str
+}
+
+// on-screen buttons for playing with a mouse or touch screen
+pub struct TouchControls {
+    buttons: Vec<TouchButton>,
+    circle: graphics::Mesh,
+    // only drawn once a touch or click has been seen
+    pub visible: bool,
+    pub held: Option<Action>
+}
+
+impl TouchControls {
+    pub const RADIUS: f32 = 45.0;
+
+    pub fn new(ctx: &mut Context) -> GameResult<TouchControls> {
+        let (w, h) = screen_size(ctx);
+        let y = h - 70.0;
+        let button = |x, action, label| TouchButton {
+            centre: na::Point2::new(x, y),
+            action,
+            label
+        };
+        let buttons = vec![
+            button(70.0, Action::Steer(Direction::Left), "<"),
+            button(180.0, Action::Steer(Direction::Right), ">"),
+            button(w - 180.0, Action::Throttle(Speed::Brake), "-"),
+            button(w - 70.0, Action::Throttle(Speed::Accelerate), "+"),
+        ];
+        let circle = graphics::Mesh::new_circle(ctx,
+            graphics::DrawMode::fill(), na::Point2::new(0.0, 0.0),
+            TouchControls::RADIUS, 0.5, graphics::WHITE)?;
+        Ok(TouchControls {
+            buttons,
+            circle,
+            visible: false,
+            held: None
+        })
+    }
+
+    pub fn hit(&self, p: na::Point2<f32>) -> Option<Action> {
+        self.buttons
+            .iter()
+            .find(|b| collide(&b.centre, TouchControls::RADIUS, &p, 0.0))
+            .map(|b| b.action)
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        if !self.visible {
+            return Ok(());
+        }
+        for button in self.buttons.iter() {
+            let alpha = if self.held == Some(button.action) { 0.5 } else { 0.2 };
+            graphics::draw(ctx, &self.circle,
+                graphics::DrawParam::new()
+                    .dest(button.centre)
+                    .color(graphics::Color::new(1.0, 1.0, 1.0, alpha)))?;
+            let text = graphics::Text::new(
+                (button.label, graphics::Font::default(), 40.0));
+            let (tw, th) = text.dimensions(ctx);
+            let corner = button.centre
+                - na::Vector2::new(tw as f32 / 2.0, th as f32 / 2.0);
+            graphics::draw(ctx, &text,
+                graphics::DrawParam::new()
+                    .dest(corner)
+                    .color(graphics::Color::new(1.0, 1.0, 1.0, 0.6)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(left: &str) -> KeyNames {
+        KeyNames {
+            left: left.to_string(),
+            right: "Right".to_string(),
+            accelerate: "Up".to_string(),
+            brake: "Down".to_string(),
+            pause: "P".to_string()
+        }
+    }
+
+    #[test]
+    fn names_become_keys() {
+        let keys = KeyBindings::try_from(names("Left")).unwrap();
+        assert!(keys == KeyBindings::arrows());
+        assert!(keys.is_preset());
+    }
+
+    #[test]
+    fn unknown_names_are_refused() {
+        assert_eq!(KeyBindings::try_from(names("Wibble")).err(),
+                   Some("unknown key \"Wibble\"".to_string()));
+    }
+
+    #[test]
+    fn missing_names_are_the_defaults() {
+        let keys: KeyBindings = toml::from_str("left = \"J\"").unwrap();
+        assert!(keys.left == keyboard::KeyCode::J);
+        assert!(keys.right == KeyBindings::new().right);
+        assert!(KeyBindings::new().clashes(&keys));
+        assert!(!KeyBindings::arrows().clashes(&keys));
+    }
+
+    #[test]
+    fn keys_round_trip_through_their_names() {
+        for (name, key) in KEY_NAMES.iter() {
+            assert_eq!(key_name(*key), *name);
+            assert!(key_from_name(name) == Some(*key));
+        }
+    }
+}
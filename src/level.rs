@@ -0,0 +1,102 @@
+use ggez::{filesystem, Context, GameError, GameResult};
+use ggez::nalgebra as na;
+use serde::Deserialize;
+
+// one level as it's written in resources/levels/, e.g.
+//
+//     { "name": "Boulders", "fruit_count": 2, "target": 300,
+//       "walled": true, "rocks": [[0.3, 0.3], [0.7, 0.7]] }
+//
+// rocks are given as fractions of the screen so a level fits any window;
+// leaving out fruit_count or rocks keeps what the rules say
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Level {
+    pub name: String,
+    pub fruit_count: Option<usize>,
+    // the score to reach to move on, counted over the whole run
+    pub target: i32,
+    // with walls the edge of the screen ends the run rather than wrapping
+    pub walled: bool,
+    rocks: Option<Vec<(f32, f32)>>
+}
+
+impl Level {
+    pub fn rocks(&self, w: f32, h: f32) -> Option<Vec<na::Point2<f32>>> {
+        self.rocks.as_ref().map(|rocks| rocks.iter()
+            .map(|(x, y)| na::Point2::new(x * w, y * h))
+            .collect())
+    }
+}
+
+// the nth level reached; after the last it goes round again from the
+// first
+pub fn nth(levels: &[Level], n: usize) -> Option<&Level> {
+    if levels.is_empty() {
+        return None;
+    }
+    levels.get(n % levels.len())
+}
+
+// the score that finishes the nth level reached, each time round needing
+// the last level's target again on top
+pub fn target(levels: &[Level], n: usize) -> i32 {
+    match (nth(levels, n), levels.last()) {
+        (Some(level), Some(last)) =>
+            level.target + (n / levels.len()) as i32 * last.target,
+        _ => 0
+    }
+}
+
+// every .json in /levels in name order; without any the game is a single
+// endless run
+pub fn load(ctx: &mut Context) -> GameResult<Vec<Level>> {
+    let mut levels = Vec::<Level>::new();
+    if !filesystem::is_dir(ctx, "/levels") {
+        return Ok(levels);
+    }
+    let mut paths: Vec<_> = filesystem::read_dir(ctx, "/levels")?
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let file = filesystem::open(ctx, &path)?;
+        let level = serde_json::from_reader(file).map_err(|e|
+            GameError::ResourceLoadError(
+                format!("{}: {}", path.display(), e)))?;
+        levels.push(level);
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels() -> Vec<Level> {
+        [100, 300].iter()
+            .map(|&target| Level {
+                target,
+                ..Level::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nth_goes_round_again() {
+        let levels = levels();
+        assert_eq!(nth(&levels, 1).map(|l| l.target), Some(300));
+        assert_eq!(nth(&levels, 2).map(|l| l.target), Some(100));
+        assert!(nth(&[], 0).is_none());
+    }
+
+    #[test]
+    fn target_adds_the_last_each_time_round() {
+        let levels = levels();
+        assert_eq!(target(&levels, 0), 100);
+        assert_eq!(target(&levels, 1), 300);
+        assert_eq!(target(&levels, 2), 400);
+        assert_eq!(target(&levels, 5), 900);
+        assert_eq!(target(&[], 3), 0);
+    }
+}
@@ -0,0 +1,108 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+
+// times the rest of the enclosing block when built with profiling
+macro_rules! profile {
+    ($name:expr) => {
+        #[cfg(feature = "profiling")]
+        let _span = tracing::info_span!($name).entered();
+    };
+}
+
+pub mod audio;
+pub mod card;
+pub mod chain;
+pub mod combo;
+pub mod config;
+pub mod debris;
+pub mod difficulty;
+pub mod display;
+pub mod effects;
+pub mod explosion;
+pub mod fruit;
+pub mod ghost;
+pub mod graph;
+pub mod hill;
+pub mod images;
+pub mod input;
+pub mod level;
+pub mod manifest;
+pub mod net;
+pub mod obstacles;
+pub mod pool;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod rival;
+pub mod rules;
+pub mod save;
+pub mod score;
+pub mod segment;
+pub mod settings;
+pub mod sim;
+pub mod snake;
+pub mod state;
+pub mod stations;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+
+pub use config::Config;
+pub use images::ImageCache;
+pub use state::State;
+
+// distance along the track between consecutive body segments
+pub const SEGMENT_SPACING: f32 = 1.0;
+
+// the simulation steps at a fixed rate, whatever the display is doing
+pub const UPDATE_RATE: u32 = 120;
+
+pub fn screen_size(ctx: &Context) -> (f32, f32) {
+    let rect = graphics::screen_coordinates(ctx);
+    (rect.w, rect.h)
+}
+
+// mouse positions come in window pixels, which differ from the
+// playfield's coordinates when the window is scaled
+pub fn window_to_screen(ctx: &Context, x: f32, y: f32) -> na::Point2<f32> {
+    let rect = graphics::screen_coordinates(ctx);
+    let (w, h) = graphics::drawable_size(ctx);
+    na::Point2::new(rect.x + x * rect.w / w, rect.y + y * rect.h / h)
+}
+
+// something went wrong that the game can carry on without
+pub fn warn(message: impl std::fmt::Display) {
+    eprintln!("warning: {}", message);
+}
+
+pub fn collide(a: &na::Point2<f32>, ra: f32, b: &na::Point2<f32>, rb: f32) -> bool {
+    let d = ra + rb;
+    na::distance_squared(a, b) < d * d
+}
+
+// dims the game behind a centred column of options with the selected one
+// marked
+pub fn draw_menu(ctx: &mut Context, options: &[String],
+                 selection: usize) -> GameResult {
+    let (w, h) = screen_size(ctx);
+    let dim = graphics::Mesh::new_rectangle(ctx,
+        graphics::DrawMode::fill(),
+        graphics::Rect::new(0.0, 0.0, w, h),
+        graphics::Color::new(0.0, 0.0, 0.0, 0.6))?;
+    graphics::draw(ctx, &dim, graphics::DrawParam::new())?;
+
+    let mut y = h / 2.0 - 20.0 * options.len() as f32;
+    for (i, option) in options.iter().enumerate() {
+        let line = if i == selection {
+            format!("> {} <", option)
+        } else {
+            option.to_string()
+        };
+        let text = graphics::Text::new(
+            (line, graphics::Font::default(), 36.0));
+        let tw = text.width(ctx) as f32;
+        graphics::draw(ctx, &text,
+            graphics::DrawParam::new()
+                .dest(na::Point2::new((w - tw) / 2.0, y)))?;
+        y += 48.0;
+    }
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use ggez::{event, graphics, input::keyboard, Context, GameResult};
+use ggez::nalgebra as na;
+use std::{fs, io, path};
+
+// resources/manifest.txt lists every resource with a hash of its
+// contents, one "hash /path" per line, so a broken install can say
+// exactly what's wrong with it
+const MANIFEST: &str = "manifest.txt";
+
+// paths are as ggez loads them, e.g. "/pop.wav"
+pub struct Report {
+    pub missing: Vec<String>,
+    pub corrupt: Vec<String>
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.corrupt.is_empty()
+    }
+
+    // images can be stood in for, sounds can't
+    pub fn can_continue(&self) -> bool {
+        self.broken().all(|p| p.ends_with(".png"))
+    }
+
+    pub fn broken(&self) -> impl Iterator<Item = &String> {
+        self.missing.iter().chain(self.corrupt.iter())
+    }
+}
+
+// without a manifest there's nothing to check against
+pub fn verify(dir: &path::Path) -> Report {
+    let mut report = Report {
+        missing: Vec::<String>::new(),
+        corrupt: Vec::<String>::new()
+    };
+    let manifest = match fs::read_to_string(dir.join(MANIFEST)) {
+        Ok(manifest) => manifest,
+        Err(_) => {
+            crate::warn(format_args!("no {} in {}, not checking resources",
+                                     MANIFEST, dir.display()));
+            return report;
+        }
+    };
+    for line in manifest.lines() {
+        let (expected, name) = match line.split_once(' ') {
+            Some(entry) => entry,
+            None => continue
+        };
+        match fs::read(dir.join(name.trim_start_matches('/'))) {
+            Ok(bytes) if format!("{:016x}", hash(&bytes)) == expected => {},
+            Ok(_) => report.corrupt.push(name.to_string()),
+            Err(_) => report.missing.push(name.to_string())
+        }
+    }
+    report
+}
+
+// run with --write-manifest after changing anything in resources
+pub fn write(dir: &path::Path) -> io::Result<()> {
+    let mut names = Vec::<String>::new();
+    list(dir, "", &mut names)?;
+    names.sort();
+
+    let mut manifest = String::new();
+    for name in names {
+        let bytes = fs::read(dir.join(&name))?;
+        manifest += &format!("{:016x} /{}\n", hash(&bytes), name);
+    }
+    fs::write(dir.join(MANIFEST), manifest)
+}
+
+// every file under `dir`, with subdirectories as part of the name
+fn list(dir: &path::Path, prefix: &str,
+        names: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir.join(prefix))? {
+        let entry = entry?;
+        let name = prefix.to_string() +
+            &entry.file_name().to_string_lossy();
+        if entry.file_type()?.is_dir() {
+            list(dir, &(name + "/"), names)?;
+        } else if name != MANIFEST {
+            names.push(name);
+        }
+    }
+    Ok(())
+}
+
+// FNV-1a, enough to catch a truncated or swapped file
+fn hash(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    h
+}
+
+// shown before the game when the resources don't match the manifest
+pub struct AssetErrors {
+    report: Report,
+    pub carry_on: bool
+}
+
+impl AssetErrors {
+    pub fn new(report: Report) -> AssetErrors {
+        AssetErrors {
+            report,
+            carry_on: false
+        }
+    }
+}
+
+impl event::EventHandler for AssetErrors {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context,
+                      keycode: keyboard::KeyCode,
+                      _keymods: keyboard::KeyMods,
+                      _repeat: bool) {
+        match keycode {
+            keyboard::KeyCode::Space | keyboard::KeyCode::Return
+                    if self.report.can_continue() => {
+                self.carry_on = true;
+                event::quit(ctx);
+            },
+            keyboard::KeyCode::Escape => event::quit(ctx),
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        graphics::clear(ctx, (0.1, 0.2, 0.3, 1.0).into());
+
+        let mut lines = vec!["Some game files are damaged:".to_string()];
+        for name in self.report.missing.iter() {
+            lines.push(format!("    {} is missing", name));
+        }
+        for name in self.report.corrupt.iter() {
+            lines.push(format!("    {} is corrupt", name));
+        }
+        lines.push(String::new());
+        if self.report.can_continue() {
+            lines.push("Space to play with placeholder images".to_string());
+        }
+        lines.push("Escape to quit".to_string());
+
+        let mut y = 20.0;
+        for line in lines {
+            let text = graphics::Text::new(
+                (line, graphics::Font::default(), 24.0));
+            graphics::draw(ctx, &text,
+                graphics::DrawParam::new().dest(na::Point2::new(20.0, y)))?;
+            y += 30.0;
+        }
+
+        graphics::present(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_fnv_1a() {
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(hash(b"ab"), hash(b"ba"));
+    }
+
+    #[test]
+    fn verify_finds_missing_and_corrupt() {
+        let dir = std::env::temp_dir()
+            .join(format!("hello_ggez_manifest_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sounds")).unwrap();
+        fs::write(dir.join("a.png"), b"image").unwrap();
+        fs::write(dir.join("sounds/b.wav"), b"sound").unwrap();
+        write(&dir).unwrap();
+        assert!(verify(&dir).is_ok());
+
+        fs::write(dir.join("a.png"), b"imagf").unwrap();
+        fs::remove_file(dir.join("sounds/b.wav")).unwrap();
+        let report = verify(&dir);
+        assert_eq!(report.corrupt, vec!["/a.png".to_string()]);
+        assert_eq!(report.missing, vec!["/sounds/b.wav".to_string()]);
+        assert!(!report.can_continue());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
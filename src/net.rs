@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::input::{Direction, Held, Speed};
+use crate::rules::Rules;
+
+// online games run in lockstep: each end sends what its player is holding
+// one byte per step, and a step is only run once both players' inputs for
+// it are in. Inputs are sent DELAY steps ahead of when they're used so a
+// little lag doesn't hold things up.
+const DELAY: usize = 6;
+
+// what the host sends when someone joins, so both ends run the same game,
+// is a Game as JSON after its length as four bytes; anything longer than
+// this isn't one
+const MAX_HANDSHAKE: usize = 64 * 1024;
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct NetConfig {
+    // what to listen on when hosting
+    pub port: u16,
+    // where to connect to when joining, e.g. "192.168.1.20:7777"
+    pub address: String
+}
+
+impl NetConfig {
+    pub fn new() -> NetConfig {
+        NetConfig {
+            port: 7777,
+            address: "127.0.0.1:7777".to_string()
+        }
+    }
+}
+
+impl Default for NetConfig {
+    fn default() -> NetConfig {
+        NetConfig::new()
+    }
+}
+
+// everything the two ends have to agree on before the first step: all of
+// the host's rules, seed included, go over so nothing that changes the
+// simulation can differ
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Game {
+    pub rules: Rules,
+    // screen coordinates, fruit and rocks are laid out to fit
+    pub width: f32,
+    pub height: f32
+}
+
+impl Game {
+    fn encode(&self) -> io::Result<Vec<u8>> {
+        let json = serde_json::to_vec(self).map_err(io::Error::other)?;
+        let mut bytes = (json.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(json);
+        Ok(bytes)
+    }
+
+    // Ok(None) until all of it's arrived, and then how many bytes it took
+    fn decode(bytes: &[u8]) -> io::Result<Option<(Game, usize)>> {
+        if bytes.len() < 4 {
+            return Ok(None);
+        }
+        let length = u32::from_le_bytes([bytes[0], bytes[1], bytes[2],
+                                         bytes[3]]) as usize;
+        if length > MAX_HANDSHAKE {
+            return Err(io::Error::other("that isn't a snake game"));
+        }
+        match bytes.get(4..4 + length) {
+            Some(json) => {
+                let game = serde_json::from_slice(json)
+                    .map_err(io::Error::other)?;
+                Ok(Some((game, 4 + length)))
+            },
+            None => Ok(None)
+        }
+    }
+}
+
+// waiting for someone to join, or for the host to say what game it is
+pub enum Connecting {
+    Hosting(TcpListener, Game),
+    Joining(TcpStream, Vec<u8>, (f32, f32))
+}
+
+impl Connecting {
+    pub fn host(port: u16, game: Game) -> io::Result<Connecting> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Connecting::Hosting(listener, game))
+    }
+
+    // only the first few seconds of connecting hold the game up; `screen`
+    // has to match the host's
+    pub fn join(address: &str, screen: (f32, f32)) -> io::Result<Connecting> {
+        let address = address.to_socket_addrs()?.next().ok_or_else(||
+            io::Error::new(io::ErrorKind::NotFound, "no such address"))?;
+        let stream = TcpStream::connect_timeout(&address,
+                                                Duration::from_secs(3))?;
+        stream.set_nonblocking(true)?;
+        Ok(Connecting::Joining(stream, Vec::<u8>::new(), screen))
+    }
+
+    // Ok(None) while still waiting
+    pub fn poll(&mut self) -> io::Result<Option<(Session, Game)>> {
+        match self {
+            Connecting::Hosting(listener, game) => {
+                let stream = match listener.accept() {
+                    Ok((stream, _)) => stream,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock =>
+                        return Ok(None),
+                    Err(e) => return Err(e)
+                };
+                stream.set_nonblocking(false)?;
+                (&stream).write_all(&game.encode()?)?;
+                Ok(Some((Session::new(stream, 0)?, game.clone())))
+            },
+            Connecting::Joining(stream, received, screen) => {
+                if read_available(stream, received)? {
+                    return Err(closed());
+                }
+                let (game, used) = match Game::decode(received)? {
+                    Some(decoded) => decoded,
+                    None => return Ok(None)
+                };
+                if (game.width, game.height) != *screen {
+                    return Err(io::Error::other(
+                        "the host's window is a different size"));
+                }
+                let mut session = Session::new(stream.try_clone()?, 1)?;
+                session.received.extend(received.drain(used..));
+                Ok(Some((session, game)))
+            }
+        }
+    }
+}
+
+// a game in progress with the other end
+pub struct Session {
+    stream: TcpStream,
+    // 0 when hosting and 1 when joined, as in State's players
+    pub player: usize,
+    // inputs sent and not yet used, oldest first
+    sent: VecDeque<u8>,
+    received: VecDeque<u8>
+}
+
+impl Session {
+    fn new(stream: TcpStream, player: usize) -> io::Result<Session> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        // both ends start as if they'd sent DELAY steps of nothing held
+        let nothing = encode(Held::new());
+        Ok(Session {
+            stream,
+            player,
+            sent: std::iter::repeat_n(nothing, DELAY).collect(),
+            received: std::iter::repeat_n(nothing, DELAY).collect()
+        })
+    }
+
+    // both players' inputs for the next step, by player, with `local` sent
+    // on for DELAY steps' time; Ok(None) means the other end is behind
+    // and the step has to wait
+    pub fn exchange(&mut self, local: Held) -> io::Result<Option<[Held; 2]>> {
+        let mut incoming = Vec::<u8>::new();
+        let closed_now = read_available(&mut self.stream, &mut incoming)?;
+        self.received.extend(incoming);
+        let remote = match self.received.pop_front() {
+            Some(remote) => decode(remote),
+            None if closed_now => return Err(closed()),
+            None => return Ok(None)
+        };
+        let byte = encode(local);
+        (&self.stream).write_all(&[byte])?;
+        self.sent.push_back(byte);
+        let local = decode(self.sent.pop_front().unwrap_or(byte));
+        Ok(Some(if self.player == 0 {
+            [local, remote]
+        } else {
+            [remote, local]
+        }))
+    }
+}
+
+// whatever's arrived without waiting, true if the other end has gone
+fn read_available(stream: &mut TcpStream,
+                  into: &mut Vec<u8>) -> io::Result<bool> {
+    let mut buffer = [0; 256];
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) => return Ok(true),
+            Ok(n) => into.extend_from_slice(&buffer[..n]),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock =>
+                return Ok(false),
+            Err(e) => return Err(e)
+        }
+    }
+}
+
+fn closed() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "the other player left")
+}
+
+fn encode(held: Held) -> u8 {
+    let direction = match held.direction {
+        Direction::Left => 0,
+        Direction::Right => 1,
+        Direction::Straight => 2
+    };
+    let speed = match held.accelerate {
+        Speed::Accelerate => 0,
+        Speed::Brake => 1,
+        Speed::Coast => 2
+    };
+    direction * 3 + speed
+}
+
+fn decode(byte: u8) -> Held {
+    Held {
+        direction: match byte / 3 {
+            0 => Direction::Left,
+            1 => Direction::Right,
+            _ => Direction::Straight
+        },
+        accelerate: match byte % 3 {
+            0 => Speed::Accelerate,
+            1 => Speed::Brake,
+            _ => Speed::Coast
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_survives_the_trip() {
+        let directions = [Direction::Left, Direction::Right,
+                          Direction::Straight];
+        let speeds = [Speed::Accelerate, Speed::Brake, Speed::Coast];
+        for direction in directions.iter() {
+            for accelerate in speeds.iter() {
+                let held = Held {
+                    direction: *direction,
+                    accelerate: *accelerate
+                };
+                let back = decode(encode(held));
+                assert!(back.direction == *direction);
+                assert!(back.accelerate == *accelerate);
+            }
+        }
+    }
+
+    #[test]
+    fn game_waits_for_the_whole_handshake() {
+        let game = Game {
+            rules: Rules::new(),
+            width: 800.0,
+            height: 600.0
+        };
+        let bytes = game.encode().unwrap();
+        for end in 0..bytes.len() {
+            assert!(Game::decode(&bytes[..end]).unwrap().is_none());
+        }
+        // the first inputs can follow straight on
+        let mut more = bytes.clone();
+        more.extend([1, 2, 3].iter());
+        let (decoded, used) = Game::decode(&more).unwrap().unwrap();
+        assert_eq!(used, bytes.len());
+        assert_eq!((decoded.width, decoded.height), (800.0, 600.0));
+        assert_eq!(decoded.rules.seed, game.rules.seed);
+    }
+
+    #[test]
+    fn oversized_handshake_is_refused() {
+        let bytes = ((MAX_HANDSHAKE + 1) as u32).to_le_bytes();
+        assert!(Game::decode(&bytes).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use ggez::{graphics, graphics::spritebatch, Context, GameResult};
+use ggez::nalgebra as na;
+use rand::{rngs::StdRng, Rng};
+
+use crate::collide;
+use crate::display::Display;
+use crate::images::ImageCache;
+
+pub struct Rock {
+    pub pos: na::Point2<f32>,
+    pub radius: f32
+}
+
+// the ways rocks get laid out
+enum Pattern {
+    Scatter,
+    Ring,
+    Columns
+}
+
+// rocks dotted around the playfield that end the run if the nose hits one
+pub struct Obstacles {
+    pub rocks: Vec<Rock>,
+    batch: spritebatch::SpriteBatch,
+    radius: f32
+}
+
+impl Obstacles {
+    pub fn new(image_cache: &mut ImageCache,
+               ctx: &mut Context) -> GameResult<Obstacles> {
+        let image = image_cache.load(ctx, "/rock.png")?;
+        let radius = image.width() as f32 / 2.0 - 2.0;
+        Ok(Obstacles {
+            rocks: Vec::<Rock>::new(),
+            batch: spritebatch::SpriteBatch::new(image),
+            radius
+        })
+    }
+
+    // about `count` rocks in one of the patterns, leaving the snake's
+    // starting run and everything in `keep_clear` free
+    pub fn lay_out(&mut self, rng: &mut StdRng, w: f32, h: f32,
+                   count: usize, keep_clear: &[na::Point2<f32>]) {
+        self.rocks.clear();
+        if count == 0 {
+            return;
+        }
+        let pattern = match rng.gen_range(0..3) {
+            0 => Pattern::Scatter,
+            1 => Pattern::Ring,
+            _ => Pattern::Columns
+        };
+        let centre = na::Point2::new(w / 2.0, h / 2.0);
+        let mut spots = Vec::<na::Point2<f32>>::new();
+        match pattern {
+            Pattern::Scatter => {
+                for _ in 0..count {
+                    spots.push(na::Point2::new(rng.gen::<f32>() * w,
+                                               rng.gen::<f32>() * h));
+                }
+            },
+            Pattern::Ring => {
+                // every third rock is left out so there's always a way
+                // through
+                let r = w.min(h) * 0.4;
+                let n = count * 3 / 2;
+                for i in (0..n).filter(|i| i % 3 != 2) {
+                    let a = i as f32 * std::f32::consts::PI * 2.0 / n as f32;
+                    spots.push(centre + na::Vector2::new(a.cos(), a.sin()) * r);
+                }
+            },
+            Pattern::Columns => {
+                // two walls with a gap in each at a random height
+                let n = (count / 2).max(1);
+                for x in [w * 0.25, w * 0.75].iter() {
+                    let gap = rng.gen_range(0..n);
+                    for i in (0..n).filter(|i| *i != gap) {
+                        let y = h * (i as f32 + 0.5) / n as f32;
+                        spots.push(na::Point2::new(*x, y));
+                    }
+                }
+            }
+        }
+        self.place(w, h, spots, keep_clear);
+    }
+
+    // rocks at the given spots, less any that would block the start
+    pub fn place(&mut self, w: f32, h: f32, spots: Vec<na::Point2<f32>>,
+                 keep_clear: &[na::Point2<f32>]) {
+        let centre = na::Point2::new(w / 2.0, h / 2.0);
+        let radius = self.radius;
+        self.rocks = spots.into_iter()
+            .filter(|p| !starting_run(p, centre, radius))
+            .filter(|p| !keep_clear.iter().any(|c| collide(p, radius, c, 60.0)))
+            .map(|pos| Rock { pos, radius })
+            .collect();
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn hit(&self, pos: &na::Point2<f32>, radius: f32) -> bool {
+        self.rocks.iter().any(|r| collide(&r.pos, r.radius, pos, radius))
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, display: &Display) -> GameResult {
+        self.batch.clear();
+        for rock in self.rocks.iter() {
+            self.batch.add(
+                graphics::DrawParam::new()
+                    .offset(na::Point2::new(0.5, 0.5))
+                    .dest(display.snap(rock.pos))
+            );
+        }
+        graphics::draw(ctx, &self.batch, graphics::DrawParam::new())
+    }
+}
+
+// the snake starts in the middle heading left, and a second player's
+// heading right, so keep that stretch open
+fn starting_run(p: &na::Point2<f32>, centre: na::Point2<f32>,
+                radius: f32) -> bool {
+    let d = p - centre;
+    collide(p, radius, &centre, 120.0) ||
+        (d.x.abs() < 300.0 && d.y.abs() < 60.0 + radius)
+}
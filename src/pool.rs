@@ -0,0 +1,105 @@
+// a fixed number of slots made up front and used over and over, so
+// nothing's allocated once the game's going; adding to a full pool
+// recycles the oldest
+pub struct Pool<T> {
+    items: Vec<T>,
+    capacity: usize,
+    // once full, the slot holding the oldest, which goes next
+    oldest: usize,
+    // the most that have been in use at once
+    peak: usize
+}
+
+impl<T> Pool<T> {
+    pub fn new(capacity: usize) -> Pool<T> {
+        Pool {
+            items: Vec::<T>::with_capacity(capacity),
+            capacity,
+            oldest: 0,
+            peak: 0
+        }
+    }
+
+    pub fn add(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items[self.oldest] = item;
+            self.oldest = (self.oldest + 1) % self.capacity;
+        } else {
+            self.items.push(item);
+        }
+        self.peak = self.peak.max(self.items.len());
+    }
+
+    // keeps the ones `f` says to, putting them back oldest first
+    pub fn retain_mut(&mut self, f: impl FnMut(&mut T) -> bool) {
+        self.items.rotate_left(self.oldest);
+        self.oldest = 0;
+        self.items.retain_mut(f);
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.oldest = 0;
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.items.iter_mut()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // in use now, at most so far, and how many there are, for the debug
+    // overlay
+    pub fn usage(&self) -> (usize, usize, usize) {
+        (self.items.len(), self.peak, self.capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_pool_recycles_the_oldest() {
+        let mut pool = Pool::new(3);
+        for i in 0..5 {
+            pool.add(i);
+        }
+        let mut items: Vec<i32> = pool.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, vec![2, 3, 4]);
+        assert_eq!(pool.usage(), (3, 3, 3));
+    }
+
+    #[test]
+    fn retain_keeps_the_oldest_first() {
+        let mut pool = Pool::new(3);
+        for i in 0..4 {
+            pool.add(i);
+        }
+        pool.retain_mut(|i| *i != 2);
+        assert_eq!(pool.iter().copied().collect::<Vec<i32>>(), vec![1, 3]);
+        // 1 is still the oldest, so it's the one to go
+        pool.add(4);
+        pool.add(5);
+        let mut items: Vec<i32> = pool.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn peak_outlasts_clearing() {
+        let mut pool = Pool::new(4);
+        pool.add(1);
+        pool.add(2);
+        pool.clear();
+        assert!(pool.is_empty());
+        assert_eq!(pool.usage(), (0, 2, 4));
+    }
+}
@@ -0,0 +1,110 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time;
+use tracing::{span, Subscriber};
+use tracing_subscriber::layer::{self, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::screen_size;
+
+// smoothed milliseconds per call, by span name
+type Timings = Arc<Mutex<HashMap<&'static str, f64>>>;
+
+// times every span as it's entered and exited
+struct Timer {
+    timings: Timings
+}
+
+impl<S> Layer<S> for Timer
+        where S: Subscriber + for<'a> LookupSpan<'a> {
+    fn on_enter(&self, id: &span::Id, ctx: layer::Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(time::Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: layer::Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return
+        };
+        let start = match span.extensions_mut().remove::<time::Instant>() {
+            Some(start) => start,
+            None => return
+        };
+        let ms = start.elapsed().as_secs_f64() * 1000.0;
+        if let Ok(mut timings) = self.timings.lock() {
+            let mean = timings.entry(span.name()).or_insert(ms);
+            *mean += (ms - *mean) * 0.05;
+        }
+    }
+}
+
+// collects the spans marked with `profile!` and draws a summary of where
+// the time is going
+pub struct Profiler {
+    timings: Timings,
+    pub visible: bool
+}
+
+impl Profiler {
+    // there can only be one, as it's installed as the global subscriber
+    pub fn install() -> Profiler {
+        let timings = Timings::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(Timer { timings: timings.clone() });
+        if tracing::subscriber::set_global_default(subscriber).is_err() {
+            crate::warn("couldn't install the profiler");
+        }
+        Profiler {
+            timings,
+            visible: false
+        }
+    }
+
+    // slowest first, with a bar per span scaled to a 60Hz frame, and
+    // under them how full each pool is, as (in use, peak, capacity)
+    pub fn draw(&self, ctx: &mut Context,
+                pools: &[(&'static str, (usize, usize, usize))])
+            -> GameResult {
+        if !self.visible {
+            return Ok(());
+        }
+        let mut timings: Vec<(&'static str, f64)> = match self.timings.lock() {
+            Ok(timings) => timings.iter().map(|(k, v)| (*k, *v)).collect(),
+            Err(_) => return Ok(())
+        };
+        timings.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+        let (_, h) = screen_size(ctx);
+        let lines = timings.len() + pools.len();
+        let mut y = h - 20.0 - 24.0 * lines as f32;
+        for (name, ms) in timings {
+            let width = (ms / (1000.0 / 60.0)).min(1.0) as f32 * 200.0;
+            if width > 0.0 {
+                let bar = graphics::Mesh::new_rectangle(ctx,
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(20.0, y, width, 20.0),
+                    graphics::Color::new(0.9, 0.4, 0.1, 0.8))?;
+                graphics::draw(ctx, &bar, graphics::DrawParam::new())?;
+            }
+            let text = graphics::Text::new((
+                format!("{} {:.3}ms", name, ms),
+                graphics::Font::default(), 18.0));
+            graphics::draw(ctx, &text,
+                graphics::DrawParam::new().dest(na::Point2::new(24.0, y)))?;
+            y += 24.0;
+        }
+        for (name, (used, peak, capacity)) in pools {
+            let text = graphics::Text::new((
+                format!("{} {}/{} peak {}", name, used, capacity, peak),
+                graphics::Font::default(), 18.0));
+            graphics::draw(ctx, &text,
+                graphics::DrawParam::new().dest(na::Point2::new(24.0, y)))?;
+            y += 24.0;
+        }
+        Ok(())
+    }
+}
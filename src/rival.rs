@@ -0,0 +1,85 @@
+use ggez::{Context, GameResult};
+use ggez::nalgebra as na;
+
+use crate::display::Display;
+use crate::images::ImageCache;
+use crate::input::{Direction, Speed};
+use crate::rules::Rules;
+use crate::snake::Snake;
+
+// how fast the rival likes to go, and how far off its heading the target
+// has to be before it brakes for the turn
+const CRUISE_SPEED: f32 = 2.5;
+const SHARP_TURN: f32 = 1.0;
+
+// a computer-driven snake after the same fruit as the player
+pub struct Rival {
+    pub snake: Snake,
+    target: Option<na::Point2<f32>>
+}
+
+impl Rival {
+    pub fn new(image_cache: &mut ImageCache, ctx: &mut Context,
+               rules: &Rules, pos: na::Point2<f32>,
+               angle: f32) -> GameResult<Rival> {
+        Rival::spawn(image_cache, ctx, rules, "/train01.png", pos, angle)
+    }
+
+    // one drawn with `image`, for when there's more than one about
+    pub fn spawn(image_cache: &mut ImageCache, ctx: &mut Context,
+                 rules: &Rules, image: &str, pos: na::Point2<f32>,
+                 angle: f32) -> GameResult<Rival> {
+        Ok(Rival {
+            snake: Snake::spawn(image_cache, ctx, rules, image, pos, angle)?,
+            target: None
+        })
+    }
+
+    // heads for `target`, or just carries on without one
+    pub fn update(&mut self, screen: (f32, f32),
+                  target: Option<na::Point2<f32>>, rules: &Rules, dt: f32) {
+        self.target = target;
+        let (direction, speed) = match target {
+            Some(target) => self.decide(screen, target),
+            None => (Direction::Straight, Speed::Coast)
+        };
+        self.snake.update(screen, &direction, &speed, rules, dt);
+    }
+
+    // steer towards the target by the shortest way round the screen,
+    // braking when it's well off to one side so it doesn't circle it
+    fn decide(&self, screen: (f32, f32),
+              target: na::Point2<f32>) -> (Direction, Speed) {
+        let (w, h) = screen;
+        let mut to = target - self.snake.head.pos;
+        to.x -= w * (to.x / w).round();
+        to.y -= h * (to.y / h).round();
+
+        let heading = self.snake.head.heading();
+        let cross = heading.x * to.y - heading.y * to.x;
+        let off = cross.atan2(heading.dot(&to));
+        let direction = if off > 0.05 {
+            Direction::Right
+        } else if off < -0.05 {
+            Direction::Left
+        } else {
+            Direction::Straight
+        };
+
+        let speed = self.snake.head.speed;
+        let throttle = if off.abs() > SHARP_TURN && speed > 1.0 {
+            Speed::Brake
+        } else if speed < CRUISE_SPEED {
+            Speed::Accelerate
+        } else {
+            Speed::Coast
+        };
+        (direction, throttle)
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, display: &Display,
+                alpha: f32) -> GameResult {
+        let look_at = self.target.unwrap_or(self.snake.head.pos);
+        self.snake.draw(ctx, display, look_at, alpha)
+    }
+}
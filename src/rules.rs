@@ -0,0 +1,187 @@
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard"
+        }
+    }
+
+    pub fn next(self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rules {
+    pub difficulty: Difficulty,
+    // radius of the tightest circle the head can turn on
+    pub min_turn_radius: f32,
+    // faster than this the turning circle widens with the speed, like a
+    // train that can't take a tight bend flat out
+    pub bend_speed: f32,
+    pub max_speed: f32,
+    // speed the snake drifts back to when neither accelerating nor braking
+    pub cruise_speed: Option<f32>,
+    // neck segments left out of self-collision, if not worked out from
+    // the turn radius
+    pub skip_segments: Option<usize>,
+    pub start_length: f32,
+    // length added per fruit
+    pub growth: f32,
+    // how many fruit are out at once
+    pub fruit_count: usize,
+    // roughly how many rocks to lay out
+    pub obstacle_count: usize,
+    // accelerating drains a boost meter, refilled by coasting and fruit
+    pub boost: bool,
+    // fractions of a full meter per second, and per fruit
+    pub boost_drain: f32,
+    pub boost_refill: f32,
+    pub boost_fruit: f32,
+    // a computer-driven snake after the fruit as well
+    pub rival: bool,
+    // a second snake on the arrow keys, and the first to crash loses
+    pub two_player: bool,
+    // stations to pull up at, picking up passengers who score at the next
+    pub stations: bool,
+    // signals that cycle green, yellow and red; running one too fast
+    // stops the snake dead
+    pub signals: bool,
+    // in a two player game, a drifting zone worth points a second to
+    // whoever's in it alone, and the first to this score wins
+    pub hill: Option<i32>,
+    // crashes a single player run can take, the last one ends it
+    pub lives: u32,
+    // the same seed gives the same fruit and explosions every run
+    pub seed: Option<u64>
+}
+
+impl Rules {
+    pub fn new() -> Rules {
+        Rules {
+            difficulty: Difficulty::Normal,
+            min_turn_radius: 100.0,
+            bend_speed: 2.5,
+            max_speed: 4.0,
+            cruise_speed: None,
+            skip_segments: None,
+            start_length: 100.0,
+            growth: 100.0,
+            fruit_count: 1,
+            obstacle_count: 8,
+            boost: false,
+            boost_drain: 0.5,
+            boost_refill: 0.12,
+            boost_fruit: 0.25,
+            rival: false,
+            two_player: false,
+            stations: false,
+            signals: false,
+            hill: None,
+            lives: 1,
+            seed: None
+        }
+    }
+
+    // max_speed is for normal play, easier and harder games are slower
+    // and faster
+    pub fn top_speed(&self) -> f32 {
+        match self.difficulty {
+            Difficulty::Easy => self.max_speed * 0.75,
+            Difficulty::Normal => self.max_speed,
+            Difficulty::Hard => self.max_speed * 1.25
+        }
+    }
+
+    // easier games turn tighter and grow slower, and only hard games have
+    // walls round the edge of the screen
+    pub fn turn_radius(&self) -> f32 {
+        match self.difficulty {
+            Difficulty::Easy => self.min_turn_radius * 0.8,
+            Difficulty::Normal => self.min_turn_radius,
+            Difficulty::Hard => self.min_turn_radius * 1.25
+        }
+    }
+
+    pub fn fruit_growth(&self) -> f32 {
+        match self.difficulty {
+            Difficulty::Easy => self.growth * 0.75,
+            Difficulty::Normal => self.growth,
+            Difficulty::Hard => self.growth * 1.5
+        }
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.difficulty != Difficulty::Hard
+    }
+
+    pub fn apply_args(&mut self, args: &[String]) {
+        if args.iter().any(|a| a == "--auto-coast") {
+            self.cruise_speed = Some(1.0);
+        }
+        if args.iter().any(|a| a == "--boost") {
+            self.boost = true;
+        }
+        if args.iter().any(|a| a == "--rival") {
+            self.rival = true;
+        }
+        if args.iter().any(|a| a == "--two-player") {
+            self.two_player = true;
+        }
+        if args.iter().any(|a| a == "--stations") {
+            self.stations = true;
+        }
+        if args.iter().any(|a| a == "--signals") {
+            self.signals = true;
+        }
+        for pair in args.windows(2) {
+            if pair[0] == "--seed" {
+                if let Ok(seed) = pair[1].parse::<u64>() {
+                    self.seed = Some(seed);
+                }
+            }
+            if pair[0] == "--lives" {
+                if let Ok(lives) = pair[1].parse::<u32>() {
+                    self.lives = lives.max(1);
+                }
+            }
+            if pair[0] == "--hill" {
+                if let Ok(target) = pair[1].parse::<i32>() {
+                    self.hill = Some(target);
+                    self.two_player = true;
+                }
+            }
+        }
+    }
+
+    // a fresh generator for each run
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules::new()
+    }
+}
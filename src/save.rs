@@ -0,0 +1,69 @@
+use ggez::{filesystem, Context, GameError, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::{fs, path};
+
+use crate::card::Stats;
+use crate::chain::Chain;
+use crate::combo::Combo;
+use crate::effects::Swirl;
+use crate::fruit::Kind;
+use crate::ghost::Replay;
+use crate::graph::RunGraph;
+use crate::rules::Difficulty;
+use crate::snake::SavedSnake;
+use crate::stations::{Aspect, SavedZone};
+
+// a single player run in progress; everything else in State is images and
+// sounds that are loaded as usual
+#[derive(Deserialize, Serialize)]
+pub struct Save {
+    // a save only loads back into a game of the same difficulty
+    pub difficulty: Difficulty,
+    pub snake: SavedSnake,
+    // position, kind and image of each fruit
+    pub fruit: Vec<(na::Point2<f32>, Kind, usize)>,
+    pub rocks: Vec<na::Point2<f32>>,
+    pub level: usize,
+    pub score: i32,
+    pub passengers: i32,
+    pub boarded_at: Option<usize>,
+    pub boost: f32,
+    // what each station and signal has seen of the snake
+    pub stations: Vec<SavedZone>,
+    pub signals: Vec<(Aspect, i32, SavedZone)>,
+    pub swirl: Swirl,
+    pub shown_length: f32,
+    pub lives: u32,
+    pub combo: Combo,
+    pub stats: Stats,
+    pub graph: RunGraph,
+    pub chain: Option<Chain>,
+    pub chain_countdown: i32,
+    // the run so far and how far the ghost had got, to carry on racing it
+    pub run: Option<Replay>,
+    pub ghost_step: Option<usize>
+}
+
+impl Save {
+    pub fn write(&self, ctx: &Context) -> GameResult {
+        let path = save_path(ctx);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string(self)
+            .map_err(|e| GameError::ResourceLoadError(e.to_string()))?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn read(ctx: &Context) -> GameResult<Save> {
+        let text = fs::read_to_string(save_path(ctx))?;
+        serde_json::from_str(&text)
+            .map_err(|e| GameError::ResourceLoadError(e.to_string()))
+    }
+}
+
+fn save_path(ctx: &Context) -> path::PathBuf {
+    filesystem::user_data_dir(ctx).join("quicksave.json")
+}
@@ -0,0 +1,44 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+
+use crate::screen_size;
+use crate::images::ImageCache;
+
+pub struct Score {
+    images: Vec<graphics::Image>,
+    pub score: i32
+}
+
+impl Score {
+    pub fn new(
+        image_cache: &mut ImageCache,
+        ctx: &mut Context) -> GameResult<Score> {
+        let mut images = Vec::<graphics::Image>::new();
+        for i in 0..=9 {
+            let s = format!("/digit0{}.png", i);
+            images.push(image_cache.load(ctx, &s)?);
+        }
+        Ok(Score {
+            images,
+            score: 0
+        })
+     }
+    
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut s = self.score;
+        let dw = self.images[0].width() as f32;
+        let (w, _h) = screen_size(ctx);
+        let mut x = w - dw - 20.0;
+        while s > 0 {
+            let digit = (s % 10) as usize;
+            graphics::draw(ctx,
+                &self.images[digit],
+                graphics::DrawParam::new()
+                    .dest(na::Point2::new(x, 20.0))
+                    )?;
+             s /= 10;
+             x -= dw;
+        }
+        Ok(())
+    }
+}
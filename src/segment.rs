@@ -0,0 +1,91 @@
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::input::{Direction, Speed};
+use crate::rules::Rules;
+
+// speeds and rates are all tuned as amounts per step at 60Hz
+const STEP: f32 = 1.0 / 60.0;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Segment {
+    pub pos: na::Point2<f32>,
+    pub angle: f32,
+    pub speed: f32
+}
+
+impl Segment {
+    pub fn new(pos: na::Point2<f32>, angle: f32, speed: f32) -> Segment {
+        Segment {
+            pos,
+            angle,
+            speed
+        }
+    }
+
+    // `dt` is the time since the last update in seconds, and the distance
+    // moved is returned; wrapping round the screen is up to the caller
+    pub fn update(&mut self, direction: &Direction, accel: &Speed,
+                  rules: &Rules, turn_radius: f32, top_speed: f32,
+                  dt: f32) -> f32 {
+        let steps = dt / STEP;
+        let distance = self.speed * steps;
+        self.translate(steps);
+        let radius = turn_radius * (self.speed / rules.bend_speed).max(1.0);
+        self.turn(direction, radius, steps);
+        self.accelerate(accel, rules.cruise_speed, top_speed, steps);
+        distance
+    }
+
+    pub fn heading(&self) -> na::Vector2::<f32> {
+        na::Rotation2::new(self.angle)
+                     * na::Vector2::new(-1.0, 0.0)
+    }
+
+    fn translate(&mut self, steps: f32) {
+        let velocity = self.heading() * self.speed;
+
+        self.pos += velocity * steps;
+    }
+
+    pub fn wrap(&mut self, min: na::Vector2<f32>, max: na::Vector2<f32>) {
+        self.pos.x = wrap(self.pos.x, min.x, max.x);
+        self.pos.y = wrap(self.pos.y, min.y, max.y);
+    }
+
+    fn turn(&mut self, direction: &Direction, radius: f32, steps: f32) {
+        // turning by speed / radius each step traces a circle of that radius
+        let rate = self.speed / radius * steps;
+        match direction {
+            Direction::Left => self.angle -= rate,
+            Direction::Right => self.angle += rate,
+            _ => {},
+        }
+    }
+
+    fn accelerate(&mut self, accel: &Speed, cruise_speed: Option<f32>,
+                  top_speed: f32, steps: f32) {
+        match (accel, cruise_speed) {
+            (Speed::Accelerate, _) => self.speed += 0.1 * steps,
+            (Speed::Brake, _) => self.speed -= 0.1 * steps,
+            (Speed::Coast, Some(cruise)) => {
+                // ease towards cruising speed, gentler than the brakes
+                let ease = 0.02 * steps;
+                let change = na::clamp(cruise - self.speed, -ease, ease);
+                self.speed += change;
+            },
+            _ => {}
+        }
+        self.speed = na::clamp(self.speed, 0.0, top_speed);
+    }
+}
+
+fn wrap(a: f32, min: f32, max: f32) -> f32 {
+    if a < min {
+        a + (max - min)
+    } else if a > max {
+        a - (max - min)
+    } else {
+        a
+    }
+}
@@ -0,0 +1,173 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+
+use crate::config::Config;
+use crate::draw_menu;
+use crate::input::{Action, Direction, KeyBindings, Speed};
+
+const VOLUME: usize = 0;
+const CONTROLS: usize = 1;
+const DIFFICULTY: usize = 2;
+const FULLSCREEN: usize = 3;
+const SCALE: usize = 4;
+const PIXEL_SNAP: usize = 5;
+const SPEED_TINT: usize = 6;
+const REDUCED_MOTION: usize = 7;
+const ATTRACT: usize = 8;
+const BACK: usize = 9;
+const OPTIONS: usize = 10;
+
+// every change is saved to the user's settings file straight away
+pub struct Settings {
+    selection: usize,
+    // difficulty can only be changed between runs, it changes the walls,
+    // the speed and the turning and the ghost is kept per difficulty
+    on_title: bool
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings {
+            selection: 0,
+            on_title: true
+        }
+    }
+
+    pub fn open(&mut self, on_title: bool) {
+        self.on_title = on_title;
+    }
+
+    // true once the player backs out
+    pub fn act(&mut self, ctx: &mut Context, config: &mut Config,
+               action: Action) -> GameResult<bool> {
+        let change = match action {
+            Action::Up | Action::Throttle(Speed::Accelerate) => {
+                self.selection = (self.selection + OPTIONS - 1) % OPTIONS;
+                return Ok(false);
+            },
+            Action::Down | Action::Throttle(Speed::Brake) => {
+                self.selection = (self.selection + 1) % OPTIONS;
+                return Ok(false);
+            },
+            Action::Pause | Action::Settings => return Ok(true),
+            Action::Confirm if self.selection == BACK => return Ok(true),
+            Action::Left | Action::Steer(Direction::Left) => -1,
+            Action::Right | Action::Steer(Direction::Right) |
+                Action::Confirm => 1,
+            _ => return Ok(false)
+        };
+
+        match self.selection {
+            VOLUME => {
+                let volume = config.volume + 0.1 * change as f32;
+                config.volume = (na::clamp(volume, 0.0, 1.0) * 10.0).round()
+                    / 10.0;
+                config.settings.volume = Some(config.volume);
+            },
+            CONTROLS => {
+                let choices = key_choices(config);
+                let current = choices.iter()
+                    .position(|k| *k == config.keys)
+                    .unwrap_or(0);
+                let n = choices.len() as i32;
+                let next = (current as i32 + change).rem_euclid(n);
+                config.keys = choices[next as usize].clone();
+                config.settings.keys = Some(config.keys.clone());
+            },
+            DIFFICULTY if !self.on_title => return Ok(false),
+            DIFFICULTY => {
+                let mut difficulty = config.rules.difficulty.next();
+                if change < 0 {
+                    difficulty = difficulty.next();
+                }
+                config.rules.difficulty = difficulty;
+                config.settings.difficulty = Some(difficulty);
+            },
+            FULLSCREEN => {
+                config.display.fullscreen = !config.display.fullscreen;
+                config.settings.fullscreen = Some(config.display.fullscreen);
+                graphics::set_fullscreen(ctx,
+                                         config.display.fullscreen_type())?;
+            },
+            // the window resizes straight away, but images only pick up
+            // nearest filtering when they're loaded at the next start
+            SCALE => {
+                let scale = config.display.scale as i32 - 1 + change;
+                config.display.scale = scale.rem_euclid(3) as u32 + 1;
+                config.settings.scale = Some(config.display.scale);
+                graphics::set_mode(ctx, config.display.window_mode())?;
+            },
+            // snapping is straight away, the filtering as for the scale
+            PIXEL_SNAP => {
+                config.display.pixel_snap = !config.display.pixel_snap;
+                config.settings.pixel_snap = Some(config.display.pixel_snap);
+            },
+            SPEED_TINT => {
+                config.display.speed_tint = !config.display.speed_tint;
+                config.settings.speed_tint = Some(config.display.speed_tint);
+            },
+            REDUCED_MOTION => {
+                config.display.reduced_motion = !config.display.reduced_motion;
+                config.settings.reduced_motion =
+                    Some(config.display.reduced_motion);
+            },
+            ATTRACT => {
+                config.display.attract = !config.display.attract;
+                config.settings.attract = Some(config.display.attract);
+            },
+            _ => return Ok(false)
+        }
+        config.save()?;
+        Ok(false)
+    }
+
+    pub fn draw(&self, ctx: &mut Context, config: &Config) -> GameResult {
+        let difficulty = if self.on_title {
+            config.rules.difficulty.name().to_string()
+        } else {
+            format!("{} (title only)", config.rules.difficulty.name())
+        };
+        let controls = if config.keys == KeyBindings::new() {
+            "WASD"
+        } else if config.keys == KeyBindings::arrows() {
+            "Arrows"
+        } else {
+            "Custom"
+        };
+        let on_off = |on| if on { "On" } else { "Off" };
+        let options = [
+            format!("Volume {:.0}%", config.volume * 100.0),
+            format!("Controls {}", controls),
+            format!("Difficulty {}", difficulty),
+            format!("Fullscreen {}", on_off(config.display.fullscreen)),
+            format!("Scale {}x", config.display.scale),
+            format!("Pixel snap {}", on_off(config.display.pixel_snap)),
+            format!("Speed tint {}", on_off(config.display.speed_tint)),
+            format!("Reduced motion {}",
+                    on_off(config.display.reduced_motion)),
+            format!("Title background {}",
+                    if config.display.attract { "Snakes" } else { "Still" }),
+            "Back".to_string()
+        ];
+        draw_menu(ctx, &options, self.selection)
+    }
+}
+
+// the presets and any bindings of the player's own, leaving out whatever
+// would share keys with player two's arrows in a two player game
+fn key_choices(config: &Config) -> Vec<KeyBindings> {
+    let second = KeyBindings::arrows();
+    [Some(KeyBindings::new()), Some(KeyBindings::arrows()),
+     config.custom_keys.clone()]
+        .iter()
+        .flatten()
+        .filter(|k| !config.rules.two_player || !k.clashes(&second))
+        .cloned()
+        .collect()
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings::new()
+    }
+}
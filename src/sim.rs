@@ -0,0 +1,212 @@
+use ggez::{graphics, timer, Context, GameResult};
+use ggez::nalgebra as na;
+use rand::{rngs::StdRng, Rng};
+
+use crate::display::Display;
+use crate::fruit::{self, Fruit, FruitSprites};
+use crate::images::ImageCache;
+use crate::obstacles::Obstacles;
+use crate::rival::Rival;
+use crate::rules::Rules;
+use crate::UPDATE_RATE;
+
+const SNAKES: usize = 6;
+// how many steps it takes for each of the game's while it's watched
+pub const SPEED_UP: u32 = 4;
+// steps before a crashed snake comes back
+const RESPAWN: i32 = 2 * UPDATE_RATE as i32;
+
+struct Entrant {
+    rival: Option<Rival>,
+    // steps until it comes back, once it's crashed
+    respawn: i32,
+    score: i32,
+    crashes: u32
+}
+
+// computer snakes playing each other on their own, to watch or have
+// going behind the title screen
+pub struct Simulation {
+    entrants: Vec<Entrant>,
+    fruit: Vec<Fruit>,
+    rules: Rules,
+    walled: bool,
+    rng: StdRng,
+    screen: (f32, f32)
+}
+
+impl Simulation {
+    pub fn new(rules: &Rules, walled: bool, sprites: &FruitSprites,
+               obstacles: &Obstacles, screen: (f32, f32)) -> Simulation {
+        let mut rng = rules.rng();
+        let (w, h) = screen;
+        let fruit = (0..SNAKES / 2)
+            .map(|_| place_fruit(sprites, obstacles, &mut rng, w, h))
+            .collect();
+        // they all set off one after the other
+        let entrants = (0..SNAKES)
+            .map(|i| Entrant {
+                rival: None,
+                respawn: i as i32 * UPDATE_RATE as i32 / 4,
+                score: 0,
+                crashes: 0
+            })
+            .collect();
+        Simulation {
+            entrants,
+            fruit,
+            rules: rules.clone(),
+            walled,
+            rng,
+            screen
+        }
+    }
+
+    pub fn step(&mut self, image_cache: &mut ImageCache, ctx: &mut Context,
+                sprites: &FruitSprites, obstacles: &Obstacles) -> GameResult {
+        let (w, h) = self.screen;
+        let dt = 1.0 / UPDATE_RATE as f32;
+        for (i, entrant) in self.entrants.iter_mut().enumerate() {
+            if entrant.rival.is_some() {
+                continue;
+            }
+            entrant.respawn -= 1;
+            if entrant.respawn <= 0 {
+                let (pos, angle) = clear_spot(obstacles, &mut self.rng, w, h);
+                let image = if i % 2 == 0 {
+                    "/train00.png"
+                } else {
+                    "/train01.png"
+                };
+                entrant.rival = Some(Rival::spawn(
+                    image_cache, ctx, &self.rules, image, pos, angle)?);
+            }
+        }
+
+        let heads: Vec<na::Point2<f32>> = self.entrants.iter()
+            .filter_map(|e| e.rival.as_ref())
+            .map(|r| r.snake.head.pos)
+            .collect();
+        for fruit in self.fruit.iter_mut() {
+            fruit.update(&heads, None, w, h, dt);
+        }
+
+        // each heads for whatever fruit's nearest it that isn't poison
+        let growth = self.rules.fruit_growth();
+        let mut crashed = vec![false; self.entrants.len()];
+        for (i, entrant) in self.entrants.iter_mut().enumerate() {
+            let rival = match &mut entrant.rival {
+                Some(rival) => rival,
+                None => continue
+            };
+            let head = rival.snake.head.pos;
+            let target = self.fruit.iter()
+                .filter(|f| f.kind != fruit::Kind::Poison)
+                .map(|f| f.pos)
+                .min_by(|a, b| na::distance_squared(a, &head)
+                    .partial_cmp(&na::distance_squared(b, &head)).unwrap());
+            rival.update((w, h), target, &self.rules, dt);
+            for f in self.fruit.iter_mut() {
+                if !rival.snake.collide(&f.pos, f.radius) {
+                    continue;
+                }
+                match f.kind {
+                    fruit::Kind::Poison => crashed[i] = true,
+                    fruit::Kind::Shrink =>
+                        rival.snake.increase_length(-growth),
+                    _ => rival.snake.increase_length(growth)
+                }
+                entrant.score += 10 + (rival.snake.head.speed * 5.0) as i32;
+                *f = place_fruit(sprites, obstacles, &mut self.rng, w, h);
+            }
+        }
+
+        for (i, entrant) in self.entrants.iter().enumerate() {
+            let snake = match &entrant.rival {
+                Some(rival) => &rival.snake,
+                None => continue
+            };
+            let hit_other = self.entrants.iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .filter_map(|(_, e)| e.rival.as_ref())
+                .any(|r| snake.hits(&r.snake));
+            crashed[i] |= hit_other ||
+                snake.collide_self(&self.rules) ||
+                obstacles.rocks.iter()
+                    .any(|r| snake.collide(&r.pos, r.radius)) ||
+                (self.walled && snake.wrapped());
+        }
+        for (entrant, crashed) in self.entrants.iter_mut().zip(crashed) {
+            if crashed {
+                entrant.rival = None;
+                entrant.respawn = RESPAWN;
+                entrant.crashes += 1;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, display: &Display,
+                sprites: &mut FruitSprites, alpha: f32) -> GameResult {
+        sprites.draw(ctx, display, &self.fruit)?;
+        for entrant in self.entrants.iter_mut() {
+            if let Some(rival) = &mut entrant.rival {
+                rival.draw(ctx, display, alpha)?;
+            }
+        }
+        Ok(())
+    }
+
+    // best first, with how fast it's all keeping up
+    pub fn draw_scoreboard(&self, ctx: &mut Context) -> GameResult {
+        let mut order: Vec<usize> = (0..self.entrants.len()).collect();
+        order.sort_by_key(|&i| -self.entrants[i].score);
+        let mut lines = vec![format!(
+            "simulation x{}   {:.0} fps   Space to leave",
+            SPEED_UP, timer::fps(ctx))];
+        for i in order {
+            let entrant = &self.entrants[i];
+            let length = entrant.rival.as_ref()
+                .map_or(0.0, |r| r.snake.current_length);
+            lines.push(format!("snake {}   {}   length {:.0}   crashes {}",
+                               i + 1, entrant.score, length,
+                               entrant.crashes));
+        }
+        let mut y = 20.0;
+        for line in lines {
+            let text = graphics::Text::new(
+                (line, graphics::Font::default(), 20.0));
+            graphics::draw(ctx, &text, graphics::DrawParam::new()
+                .dest(na::Point2::new(20.0, y)))?;
+            y += 26.0;
+        }
+        Ok(())
+    }
+}
+
+fn place_fruit(sprites: &FruitSprites, obstacles: &Obstacles,
+               rng: &mut StdRng, w: f32, h: f32) -> Fruit {
+    let mut fruit = Fruit::new(sprites, rng, w, h, 0);
+    for _ in 0..10 {
+        if !obstacles.hit(&fruit.pos, fruit.radius) {
+            break;
+        }
+        fruit = Fruit::new(sprites, rng, w, h, 0);
+    }
+    fruit
+}
+
+// away from the edges and the rocks, if somewhere turns up quickly
+fn clear_spot(obstacles: &Obstacles, rng: &mut StdRng, w: f32,
+              h: f32) -> (na::Point2<f32>, f32) {
+    let mut pos = na::Point2::new(w / 2.0, h / 2.0);
+    for _ in 0..20 {
+        pos = na::Point2::new(rng.gen_range(w * 0.1..w * 0.9),
+                              rng.gen_range(h * 0.1..h * 0.9));
+        if !obstacles.hit(&pos, 100.0) {
+            break;
+        }
+    }
+    (pos, rng.gen_range(0.0..std::f32::consts::PI * 2.0))
+}
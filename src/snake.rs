@@ -0,0 +1,408 @@
+use ggez::{graphics, graphics::spritebatch, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::{collide, screen_size, SEGMENT_SPACING};
+use crate::difficulty;
+use crate::display::Display;
+use crate::effects::Flash;
+use crate::images::ImageCache;
+use crate::input::{Direction, Speed};
+use crate::rules::Rules;
+use crate::segment::Segment;
+
+// the longest a snake can grow
+pub const MAX_LENGTH: f32 = 10000.0;
+
+// the parts of a snake that change as it moves, for saving
+#[derive(Deserialize, Serialize)]
+pub struct SavedSnake {
+    head: Segment,
+    body: VecDeque<Segment>,
+    desired_length: f32,
+    current_length: f32,
+    travelled: f32,
+    overdrive: f32
+}
+
+pub struct Snake {
+    image: graphics::Image,
+    batch: spritebatch::SpriteBatch,
+    eye: graphics::Mesh,
+    pupil: graphics::Mesh,
+    nose: na::Point2<f32>,
+    head_radius: f32,
+    pub head: Segment,
+    // the head as it was a step ago, for drawing in between steps
+    prev: Segment,
+    body: VecDeque<Segment>,
+    desired_length: f32,
+    pub current_length: f32,
+    travelled: f32,
+    max_speed: f32,
+    // seconds left of a raised top speed
+    overdrive: f32,
+    pub flash: Flash,
+    // below 1.0 for a see-through ghost
+    pub opacity: f32
+}
+
+impl Snake {
+    pub fn new(
+        image_cache: &mut ImageCache,
+        ctx: &mut Context,
+        rules: &Rules) -> GameResult<Snake> {
+        let (w, h) = screen_size(ctx);
+        Snake::spawn(image_cache, ctx, rules, "/train00.png",
+                     na::Point2::new(w / 2.0, h / 2.0), 0.0)
+    }
+
+    // a snake drawn with `image` starting at `pos`, facing `angle`
+    pub fn spawn(
+        image_cache: &mut ImageCache,
+        ctx: &mut Context,
+        rules: &Rules,
+        image: &str,
+        pos: na::Point2<f32>,
+        angle: f32) -> GameResult<Snake> {
+        let image = image_cache.load(ctx, image)?;
+
+        let head_radius = (image.width() as f32) * 0.1 / 2.0;
+        let origin = na::Point2::new(0.0, 0.0);
+        let eye = graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(),
+            origin, 7.0, 0.5, graphics::WHITE)?;
+        let pupil = graphics::Mesh::new_circle(ctx, graphics::DrawMode::fill(),
+            origin, 3.5, 0.5, graphics::BLACK)?;
+
+        let head = Segment::new(pos, angle, 1.0);
+
+        Ok(Snake {
+            batch: spritebatch::SpriteBatch::new(image.clone()),
+            eye,
+            pupil,
+            image,
+            nose: pos,
+            head_radius,
+            prev: head.clone(),
+            head,
+            body: VecDeque::<Segment>::new(),
+            desired_length: rules.start_length,
+            current_length: 0.0,
+            travelled: 0.0,
+            max_speed: rules.top_speed(),
+            overdrive: 0.0,
+            flash: Flash::new(),
+            opacity: 1.0
+        })
+    }
+
+    pub fn save(&self) -> SavedSnake {
+        SavedSnake {
+            head: self.head.clone(),
+            body: self.body.clone(),
+            desired_length: self.desired_length,
+            current_length: self.current_length,
+            travelled: self.travelled,
+            overdrive: self.overdrive
+        }
+    }
+
+    pub fn restore(&mut self, saved: SavedSnake) {
+        self.prev = saved.head.clone();
+        self.head = saved.head;
+        self.body = saved.body;
+        self.desired_length = saved.desired_length;
+        self.current_length = saved.current_length;
+        self.travelled = saved.travelled;
+        self.overdrive = saved.overdrive;
+        self.nose = self.head.pos + self.head.heading() * self.head_radius;
+    }
+
+    pub fn collide(&self, b: &na::Point2<f32>, rb: f32) -> bool {
+        profile!("collision");
+        collide(&self.nose, self.head_radius, b, rb)
+    }
+
+    pub fn collide_self(&self, rules: &Rules) -> bool {
+        profile!("collision");
+        self.touches_self(rules, self.head_radius / 2.0)
+    }
+
+    // the nose running into any part of another snake
+    pub fn hits(&self, other: &Snake) -> bool {
+        self.hits_head(other) || self.cuts(other).is_some()
+    }
+
+    pub fn hits_head(&self, other: &Snake) -> bool {
+        profile!("collision");
+        collide(&self.nose, self.head_radius,
+                &other.head.pos, other.head_radius)
+    }
+
+    // the first of another snake's body segments the nose is over
+    pub fn cuts(&self, other: &Snake) -> Option<usize> {
+        profile!("collision");
+        other.body.iter().position(|s| collide(&self.nose, self.head_radius,
+                                               &s.pos, other.head_radius / 2.0))
+    }
+
+    // severed wherever `other`'s nose is over the body, keeping the part
+    // nearest the head and handing back the rest; it's cut until the nose
+    // is clear so the next step doesn't take another bite
+    pub fn cut_by(&mut self, other: &Snake) -> Option<VecDeque<Segment>> {
+        let mut tail = VecDeque::<Segment>::new();
+        while let Some(at) = other.cuts(self) {
+            let kept = self.body.split_off(at + 1);
+            tail.append(&mut std::mem::replace(&mut self.body, kept));
+        }
+        if tail.is_empty() {
+            return None;
+        }
+        self.current_length = self.body.len() as f32 * SEGMENT_SPACING;
+        self.desired_length = self.current_length;
+        Some(tail)
+    }
+
+    // brushing past the body without quite hitting it
+    pub fn near_miss(&self, rules: &Rules) -> bool {
+        profile!("collision");
+        self.touches_self(rules, self.head_radius * 1.5)
+    }
+
+    fn touches_self(&self, rules: &Rules, radius: f32) -> bool {
+        // the track laid over the last turning radius is never checked, a
+        // long snake is let off less of it, but never the segments the
+        // nose overlaps running straight
+        let reach = self.head_radius * 2.0 + radius;
+        let neck = (reach / SEGMENT_SPACING).ceil() as usize;
+        let grace = difficulty::grace(rules.skip_segments.unwrap_or(
+            (rules.turn_radius() / SEGMENT_SPACING) as usize),
+            self.desired_length, neck);
+        self.body
+            .iter()
+            .rev()
+            .enumerate()
+            .any(|(i, s)| i > grace
+                        && collide(&self.nose,
+                                     self.head_radius,
+                                     &s.pos,
+                                     radius))
+    }
+
+    // the head jumped to the other side of the screen this step
+    pub fn wrapped(&self) -> bool {
+        (self.head.pos - self.prev.pos).norm() > self.head_radius
+    }
+
+    pub fn segments(&mut self) -> Option<std::slice::Iter<'_, Segment>> {
+        self.body.make_contiguous();
+        if let (slice, &[]) = self.body.as_slices() {
+            Some(slice.iter())
+        } else {
+            None
+        }
+    }
+
+    pub fn update(&mut self, screen:(f32, f32),
+                  direction: &Direction,
+                  accelerate: &Speed,
+                  rules: &Rules,
+                  dt: f32) {
+        let (w, h) = screen;
+        let bounds = (na::Vector2::<f32>::new(0.0, 0.0),
+                      na::Vector2::<f32>::new(w, h));
+        self.prev = self.head.clone();
+        self.flash.update();
+        let top_speed = if self.overdrive > 0.0 {
+            self.overdrive -= dt;
+            rules.top_speed() * 1.5
+        } else {
+            rules.top_speed()
+        };
+        let turn_radius = difficulty::turn_radius(rules.turn_radius(),
+                                                  self.desired_length);
+        let distance = self.head.update(direction, accelerate, rules,
+                                        turn_radius, top_speed, dt);
+        self.head.wrap(bounds.0, bounds.1);
+        self.lay_track(bounds, distance);
+
+        while self.current_length > self.desired_length {
+            if self.body.pop_front().is_some() {
+                self.current_length -= SEGMENT_SPACING;
+            }
+        }
+
+        self.nose = self.head.pos + self.head.heading() * self.head_radius;
+    }
+
+    // drop body segments at even arc-length intervals behind the head,
+    // however fast or slow it's going, so the sprites never bunch up
+    fn lay_track(&mut self, screen: (na::Vector2<f32>, na::Vector2<f32>),
+                 distance: f32) {
+        self.travelled += distance;
+        while self.travelled >= SEGMENT_SPACING {
+            self.travelled -= SEGMENT_SPACING;
+            let mut s = self.head.clone();
+            s.pos -= s.heading() * self.travelled;
+            s.wrap(screen.0, screen.1);
+            self.body.push_back(s);
+            self.current_length += SEGMENT_SPACING;
+        }
+    }
+
+    // put the head straight where a recorded run had it and lay the body
+    // behind it as usual; a jump bigger than wrapping round the screen
+    // means the run started again in the middle, so the body does too
+    pub fn follow(&mut self, screen: (f32, f32), pos: na::Point2<f32>,
+                  angle: f32, length: f32) {
+        let (w, h) = screen;
+        let mut moved = pos - self.head.pos;
+        moved.x -= w * (moved.x / w).round();
+        moved.y -= h * (moved.y / h).round();
+        self.prev = self.head.clone();
+        self.head.pos = pos;
+        self.head.angle = angle;
+        self.desired_length = length;
+        if moved.norm() > self.head_radius * 2.0 {
+            self.prev = self.head.clone();
+            self.body.clear();
+            self.current_length = 0.0;
+            self.travelled = 0.0;
+        } else {
+            self.lay_track((na::Vector2::new(0.0, 0.0),
+                            na::Vector2::new(w, h)), moved.norm());
+        }
+        while self.current_length > self.desired_length {
+            if self.body.pop_front().is_some() {
+                self.current_length -= SEGMENT_SPACING;
+            }
+        }
+        self.nose = self.head.pos + self.head.heading() * self.head_radius;
+    }
+
+    pub fn overdrive(&mut self, seconds: f32) {
+        self.overdrive = seconds;
+    }
+
+    pub fn desired_length(&self) -> f32 {
+        self.desired_length
+    }
+
+    pub fn increase_length(&mut self, length: f32) {
+        self.desired_length = na::clamp(self.desired_length + length,
+                                        0.0, MAX_LENGTH);
+    }
+    
+    // `alpha` is how far through the next step to draw the head; the body
+    // is laid on a fixed track so it only has to stop short of the head
+    pub fn draw(&mut self, ctx: &mut Context, display: &Display,
+                look_at: na::Point2<f32>, alpha: f32) -> GameResult {
+        let head = self.blended_head(alpha);
+        let ahead = self.ahead_of(alpha);
+        self.fill_batch(display, &head, ahead);
+        graphics::draw(ctx, &self.batch, graphics::DrawParam::new())?;
+        self.draw_eyes(ctx, display, &head, look_at)?;
+        Ok(())
+    }
+
+    // leaves off the newest `ahead` segments
+    fn fill_batch(&mut self, display: &Display, head: &Segment,
+                  ahead: usize) {
+        profile!("batching");
+        self.batch.clear();
+
+        let w = self.image.width();
+        let scale = 2.0 / ( w as f32);
+
+        let sw = SEGMENT_SPACING * scale;
+        let mut f = 0.0;
+        let shown = self.body.len().saturating_sub(ahead);
+        for s in self.body.iter().take(shown) {
+            let off = 1.0 - (f + sw).rem_euclid(0.9);
+            let mut color = self.flash.tint(if display.speed_tint {
+                speed_color(s.speed, self.max_speed)
+            } else {
+                graphics::WHITE
+            });
+            color.a *= self.opacity;
+            self.batch.add(
+                graphics::DrawParam::new()
+                    .src(graphics::Rect::new(off, 0.0, sw, 1.0))
+                    .offset(na::Point2::new(0.5, 0.5))
+                    .dest(display.snap(s.pos))
+                    .rotation(s.angle)
+                    .color(color)
+            );
+            f += sw;
+        }
+
+        self.batch.add(
+            graphics::DrawParam::new()
+                .src(graphics::Rect::new(0.0, 0.0, 0.1, 1.0))
+                .offset(na::Point2::new(1.0, 0.5))
+                .dest(display.snap(head.pos))
+                .rotation(head.angle)
+                .color(self.flash.tint(self.see_through())),
+        );
+    }
+
+    // a head that's just wrapped to the other side of the screen is drawn
+    // where it is rather than blended across it
+    fn blended_head(&self, alpha: f32) -> Segment {
+        let mut head = self.head.clone();
+        let jump = self.head.pos - self.prev.pos;
+        if jump.norm() < self.head_radius {
+            head.pos = self.prev.pos + jump * alpha;
+            head.angle = self.prev.angle +
+                (self.head.angle - self.prev.angle) * alpha;
+        }
+        head
+    }
+
+    // how many of the segments laid in the last step are still in front of
+    // the head drawn at `alpha`, to keep the neck from poking out past it
+    fn ahead_of(&self, alpha: f32) -> usize {
+        let jump = (self.head.pos - self.prev.pos).norm();
+        if jump >= self.head_radius {
+            return 0;
+        }
+        // the newest segment's `travelled` behind the head, and each one
+        // before it another SEGMENT_SPACING
+        let behind = (1.0 - alpha) * jump;
+        ((behind - self.travelled) / SEGMENT_SPACING).ceil().max(0.0) as usize
+    }
+
+    fn see_through(&self) -> graphics::Color {
+        graphics::Color::new(1.0, 1.0, 1.0, self.opacity)
+    }
+
+    // a pair of eyes on the front of the head that follow `look_at`
+    fn draw_eyes(&mut self, ctx: &mut Context, display: &Display,
+                 head: &Segment, look_at: na::Point2<f32>) -> GameResult {
+        let heading = head.heading();
+        let side = na::Vector2::new(-heading.y, heading.x);
+        let centre = head.pos + heading * self.head_radius * 1.4;
+
+        for eye in [centre + side * 12.0, centre - side * 12.0].iter() {
+            let bearing = look_at - eye;
+            let glance = if bearing.norm() > 0.0 {
+                bearing.normalize() * 3.5
+            } else {
+                bearing
+            };
+            let color = self.see_through();
+            graphics::draw(ctx, &self.eye, graphics::DrawParam::new()
+                .dest(display.snap(*eye)).color(color))?;
+            graphics::draw(ctx, &self.pupil, graphics::DrawParam::new()
+                .dest(display.snap(eye + glance)).color(color))?;
+        }
+        Ok(())
+    }
+}
+
+// blue when crawling through to red at full speed
+pub fn speed_color(speed: f32, max_speed: f32) -> graphics::Color {
+    let t = speed / max_speed;
+    graphics::Color::new(0.4 + 0.6 * t, 0.6 - 0.2 * t, 1.0 - 0.7 * t, 1.0)
+}
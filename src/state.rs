@@ -0,0 +1,2129 @@
+use core::time;
+use ggez::{event, graphics, input::keyboard, timer, Context, GameResult};
+use ggez::nalgebra as na;
+use rand::{rngs::StdRng, Rng};
+use std::collections::VecDeque;
+
+use crate::{draw_menu, screen_size, warn, window_to_screen, UPDATE_RATE};
+use crate::audio::{Audio, Music, Sound, Track};
+use crate::card::{Results, Stats};
+use crate::chain::{self, Chain, Outcome};
+use crate::combo::Combo;
+use crate::config::Config;
+use crate::debris::{self, Debris};
+use crate::difficulty;
+use crate::effects::{Label, Popup, SpeedLines, Swirl};
+use crate::explosion::Explosion;
+use crate::fruit::{self, Fruit, FruitSprites};
+use crate::ghost::{Ghost, Replay};
+use crate::graph::RunGraph;
+use crate::hill::Hill;
+use crate::images::ImageCache;
+use crate::input::{self, Action, Direction, Held, KeyBindings, Speed,
+                   TouchControls};
+use crate::level::{self, Level};
+use crate::net::{self, Connecting, Session};
+use crate::obstacles::{Obstacles, Rock};
+use crate::pool::Pool;
+#[cfg(feature = "profiling")]
+use crate::profiling::Profiler;
+use crate::rival::Rival;
+use crate::rules::Rules;
+use crate::save::Save;
+use crate::score::Score;
+use crate::segment::Segment;
+use crate::settings::Settings;
+use crate::sim::{self, Simulation};
+use crate::snake::{self, Snake};
+use crate::stations::{self, Signal, Station};
+#[cfg(feature = "telemetry")]
+use crate::telemetry::{self, Command, Telemetry};
+
+#[derive(Clone, Copy, PartialEq)]
+enum PlayState {
+    Space,
+    // 3-2-1 with the snake held still, before play
+    Countdown,
+    Play,
+    Paused,
+    Settings,
+    // between one level and the next
+    LevelComplete,
+    // hosting or joining an online game
+    Connecting,
+    // watching computer snakes play each other
+    Simulation,
+    Dead,
+    // how the run went, until a key's pressed
+    GameOver
+}
+
+// steps of 3-2-1 before a run starts
+const COUNTDOWN: i32 = 3 * UPDATE_RATE as i32;
+
+// steps of the crash before respawning or the game's over, counted rather
+// than timed so online players both move on together
+const DEAD_STEPS: i32 = 3 * UPDATE_RATE as i32;
+
+// steps before a crashed rival comes back
+const RIVAL_RESPAWN: i32 = 3 * UPDATE_RATE as i32;
+
+// steps between one chain ending and the next starting
+const CHAIN_EVERY: i32 = 30 * UPDATE_RATE as i32;
+
+// the length bar fills up towards each multiple of this
+const LENGTH_MILESTONE: f32 = 500.0;
+
+// the most points floating up at once
+const MAX_POPUPS: usize = 32;
+
+const PAUSE_MENU: [&str; 4] = ["Resume", "Restart", "Settings", "Quit"];
+const TITLE_MENU: [&str; 4] = ["Play", "Host online game", "Join online game",
+                               "Watch computer snakes"];
+
+pub struct State {
+    play_state: PlayState,
+    config: Config,
+    settings: Settings,
+    // where to go back to when the settings screen closes
+    settings_from: PlayState,
+    image_cache: ImageCache,
+    // everything random in a run comes from here
+    rng: StdRng,
+    touch: TouchControls,
+    audio: Audio,
+    music: Music,
+    space_image: graphics::Image,
+    score: Score,
+    snake: Snake,
+    rival: Option<Rival>,
+    // steps until a crashed rival comes back
+    rival_respawn: i32,
+    // the second player's snake in a two player game
+    second: Option<Snake>,
+    second_score: i32,
+    second_keys: KeyBindings,
+    // tails cut off in a two player game
+    debris: Vec<Debris>,
+    // king of the hill, with two players
+    hill: Option<Hill>,
+    // lives left in a single player run, counting the one being played
+    lives: u32,
+    // which player crashed, 0 or 1, or none when both did at once
+    loser: Option<usize>,
+    // what each player is holding down
+    held: [Held; 2],
+    // each player's meter, 0.0 empty to 1.0 full, only used with the boost
+    // rules
+    boost: [f32; 2],
+    // each player's multiplier for fruit eaten in quick succession
+    combos: [Combo; 2],
+    // points floating up from where they were scored
+    popups: Pool<Popup>,
+    // the line along the top, the combo multiplier and the next length
+    // milestone
+    hud: Label,
+    combo_label: Label,
+    milestone_label: Label,
+    speed_lines: SpeedLines,
+    // a gravity well pulling the fruit in on whoever ate it
+    swirl: Swirl,
+    // the length as the bar shows it, easing after the real one
+    shown_length: f32,
+    graph: RunGraph,
+    stats: Stats,
+    // the best single player run so far, raced against
+    ghost: Option<Ghost>,
+    // this run so far, to become the ghost if it's the best
+    run: Option<Replay>,
+    // shown on the title screen until the next run ends
+    last_run: Option<Results>,
+    // where the last score card went
+    card_saved: Option<String>,
+    // why the last quick save or load didn't work, and when, shown for a
+    // moment
+    notice: Option<(String, time::Duration)>,
+    fruit: Vec<Fruit>,
+    fruit_sprites: FruitSprites,
+    chain: Option<Chain>,
+    chain_numbers: Vec<graphics::Text>,
+    // steps until the next chain turns up
+    chain_countdown: i32,
+    obstacles: Obstacles,
+    levels: Vec<Level>,
+    // which of `levels` is being played
+    level: usize,
+    // steps left of the level complete screen
+    transition: i32,
+    // steps left before the snake's let go
+    countdown: i32,
+    walls: graphics::Mesh,
+    stations: Vec<Station>,
+    signals: Vec<Signal>,
+    // each player's cargo and the station it was picked up from
+    passengers: [i32; 2],
+    boarded_at: [Option<usize>; 2],
+    pause_selection: usize,
+    title_selection: usize,
+    connecting: Option<Connecting>,
+    // an online game, which lasts for one run
+    session: Option<Session>,
+    // the rules to go back to once it's over
+    offline_rules: Option<Rules>,
+    // why the last online game ended, or couldn't start
+    net_message: Option<String>,
+    // computer snakes, watched or behind the title screen
+    simulation: Option<Simulation>,
+    restart: bool,
+    // steps left of being dead
+    dead_steps: i32,
+    explosion: Explosion,
+    #[cfg(feature = "telemetry")]
+    telemetry: Telemetry,
+    #[cfg(feature = "profiling")]
+    profiler: Profiler
+}
+
+impl State {
+    pub fn new(ctx: &mut Context, config: Config,
+               mut image_cache: ImageCache) -> GameResult<State> {
+        config.display.apply(ctx)?;
+
+        let touch = TouchControls::new(ctx)?;
+        let mut audio = Audio::new(ctx)?;
+        let mut music = Music::new(ctx)?;
+        audio.volume = config.volume;
+        music.volume = config.volume;
+        let space_image = image_cache.load(ctx, "/space0.png")?;
+        let (w, h) = screen_size(ctx);
+
+        #[cfg(feature = "telemetry")]
+        let telemetry = Telemetry::new(config.telemetry,
+                                       config.remote_control)?;
+        let score = Score::new(&mut image_cache, ctx)?;
+        let snake = Snake::new(&mut image_cache, ctx, &config.rules)?;
+        let fruit_sprites = FruitSprites::new(&mut image_cache, ctx)?;
+        let obstacles = Obstacles::new(&mut image_cache, ctx)?;
+        let levels = level::load(ctx)?;
+        let explosion = Explosion::new(&mut image_cache, ctx)?;
+        let walls = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::stroke(6.0),
+            graphics::Rect::new(0.0, 0.0, w, h),
+            graphics::Color::new(0.8, 0.6, 0.4, 1.0))?;
+
+        let mut state = State {
+            play_state: PlayState::Space,
+            rng: config.rules.rng(),
+            config,
+            settings: Settings::new(),
+            settings_from: PlayState::Space,
+            image_cache,
+            touch,
+            audio,
+            music,
+            space_image,
+            score,
+            snake,
+            rival: None,
+            rival_respawn: 0,
+            second: None,
+            second_score: 0,
+            second_keys: KeyBindings::arrows(),
+            debris: Vec::<Debris>::new(),
+            lives: 1,
+            hill: None,
+            loser: None,
+            held: [Held::new(); 2],
+            boost: [1.0; 2],
+            combos: [Combo::new(), Combo::new()],
+            popups: Pool::new(MAX_POPUPS),
+            hud: Label::new(24.0),
+            combo_label: Label::new(64.0),
+            milestone_label: Label::new(16.0),
+            speed_lines: SpeedLines::new(),
+            swirl: Swirl::new(),
+            shown_length: 0.0,
+            graph: RunGraph::new(),
+            stats: Stats::new(),
+            ghost: None,
+            run: None,
+            last_run: None,
+            card_saved: None,
+            notice: None,
+            fruit: Vec::<Fruit>::new(),
+            fruit_sprites,
+            chain: None,
+            chain_numbers: chain::numbers(),
+            chain_countdown: CHAIN_EVERY,
+            obstacles,
+            levels,
+            level: 0,
+            transition: 0,
+            countdown: 0,
+            walls,
+            stations: Vec::<Station>::new(),
+            signals: Vec::<Signal>::new(),
+            passengers: [0; 2],
+            boarded_at: [None; 2],
+            pause_selection: 0,
+            title_selection: 0,
+            connecting: None,
+            session: None,
+            offline_rules: None,
+            net_message: None,
+            simulation: None,
+            restart: false,
+            dead_steps: 0,
+            explosion,
+            #[cfg(feature = "telemetry")]
+            telemetry,
+            #[cfg(feature = "profiling")]
+            profiler: Profiler::install()
+        };
+        // the rocks and fruit are laid out here
+        state.reset(ctx)?;
+        Ok(state)
+    }
+
+    // back to a fresh snake with nothing scored, and with a seed set the
+    // same rocks and fruit as last time
+    fn reset(&mut self, ctx: &mut Context) -> GameResult {
+        self.rng = self.config.rules.rng();
+        self.level = 0;
+        self.dead_steps = 0;
+        self.explosion.stop();
+        self.score.score = 0;
+        self.second_score = 0;
+        self.lives = self.config.rules.lives.max(1);
+        self.loser = None;
+        self.passengers = [0; 2];
+        self.boarded_at = [None; 2];
+        self.boost = [1.0; 2];
+        self.combos = [Combo::new(), Combo::new()];
+        self.popups.clear();
+        self.shown_length = 0.0;
+        self.graph = RunGraph::new();
+        self.stats = Stats::new();
+        self.run = Some(Replay::new());
+        let difficulty = self.config.rules.difficulty;
+        self.ghost = match Replay::read(ctx, difficulty) {
+            Ok(replay) if !self.config.rules.two_player =>
+                Some(Ghost::new(&mut self.image_cache, ctx,
+                                &self.config.rules, replay)?),
+            _ => None
+        };
+        self.start_level(ctx)
+    }
+
+    // a single player run that beats the ghost becomes the new one
+    fn keep_best(&mut self, ctx: &Context) {
+        let best = self.ghost.as_ref().map_or(0, |g| g.score());
+        if self.second.is_some() || self.score.score <= best {
+            return;
+        }
+        if let Some(mut run) = self.run.take() {
+            run.score = self.score.score;
+            if let Err(e) = run.write(ctx, self.config.rules.difficulty) {
+                warn(format_args!("couldn't save the best run: {}", e));
+            }
+        }
+    }
+
+    // lay out the current level and put a fresh snake back in the middle;
+    // whatever the level leaves out comes from the rules
+    fn start_level(&mut self, ctx: &mut Context) -> GameResult {
+        let (w, h) = screen_size(ctx);
+        self.stations = if self.config.rules.stations {
+            vec![
+                Station::new(ctx, na::Point2::new(w * 0.2, h * 0.25))?,
+                Station::new(ctx, na::Point2::new(w * 0.8, h * 0.75))?,
+            ]
+        } else {
+            Vec::<Station>::new()
+        };
+        self.signals = if self.config.rules.signals {
+            vec![
+                Signal::new(ctx, na::Point2::new(w * 0.75, h * 0.25))?,
+                Signal::new(ctx, na::Point2::new(w * 0.25, h * 0.75))?,
+            ]
+        } else {
+            Vec::<Signal>::new()
+        };
+        let keep_clear: Vec<na::Point2<f32>> = self.stations.iter()
+            .map(|s| s.zone.centre())
+            .chain(self.signals.iter().map(|s| s.zone.centre()))
+            .collect();
+        let level = level::nth(&self.levels, self.level);
+        match level.and_then(|l| l.rocks(w, h)) {
+            Some(rocks) => self.obstacles.place(w, h, rocks, &keep_clear),
+            None => self.obstacles.lay_out(&mut self.rng, w, h,
+                                           self.config.rules.obstacle_count,
+                                           &keep_clear)
+        }
+        let fruit_count = level.and_then(|l| l.fruit_count)
+            .unwrap_or(self.config.rules.fruit_count);
+        let score = self.score.score;
+        self.fruit = (0..fruit_count)
+            .map(|_| self.place_fruit(w, h, score))
+            .collect();
+        self.snake = Snake::new(&mut self.image_cache, ctx,
+                                &self.config.rules)?;
+        // the second player starts alongside heading the other way
+        self.second = if self.config.rules.two_player {
+            Some(Snake::spawn(&mut self.image_cache, ctx, &self.config.rules,
+                              "/train01.png",
+                              na::Point2::new(w / 2.0 + 60.0, h / 2.0),
+                              std::f32::consts::PI)?)
+        } else {
+            None
+        };
+        self.rival = None;
+        self.rival_respawn = 0;
+        self.debris.clear();
+        self.hill = match (&self.second, self.config.rules.hill) {
+            (Some(_), Some(_)) => Some(Hill::new(&mut self.rng, w, h)),
+            _ => None
+        };
+        self.chain = None;
+        self.chain_countdown = CHAIN_EVERY;
+        self.swirl = Swirl::new();
+        Ok(())
+    }
+
+    // the title screen, or the game over screen in front of it
+    fn on_title(&self) -> bool {
+        self.play_state == PlayState::Space ||
+            self.play_state == PlayState::GameOver
+    }
+
+    fn walled(&self) -> bool {
+        !self.config.rules.wraps() ||
+            level::nth(&self.levels, self.level).is_some_and(|l| l.walled)
+    }
+
+    // the levels go round and round, so there's always a next one
+    fn level_complete(&self) -> bool {
+        !self.levels.is_empty() &&
+            self.score.score >= level::target(&self.levels, self.level)
+    }
+
+    // how full each of the pools is, for the debug overlay
+    #[cfg(feature = "profiling")]
+    fn pool_usage(&self) -> [(&'static str, (usize, usize, usize)); 2] {
+        [("pops", self.explosion.usage()),
+         ("popups", self.popups.usage())]
+    }
+
+    // keyboard and gamepad input both end up here
+    fn act(&mut self, ctx: &mut Context, action: Action) {
+        if action == Action::Quit {
+            event::quit(ctx);
+        }
+
+        match self.play_state {
+            PlayState::Space if action == Action::Confirm =>
+                self.title_choice(ctx),
+            PlayState::Simulation
+                    if action == Action::Confirm || action == Action::Pause =>
+                self.play_state = PlayState::Space,
+            PlayState::Connecting if action == Action::Confirm => {
+                self.connecting = None;
+                self.play_state = PlayState::Space;
+            },
+            PlayState::Space if action == Action::Settings =>
+                self.open_settings(),
+            PlayState::GameOver if action == Action::Confirm => {
+                self.play_state = PlayState::Space;
+                self.audio.queue(Sound::Title);
+            },
+            PlayState::Space | PlayState::Dead | PlayState::GameOver
+                    if action == Action::Share => self.save_card(ctx),
+            PlayState::Space | PlayState::Play | PlayState::Paused
+                    if action == Action::QuickLoad => self.quick_load(ctx),
+            PlayState::Play | PlayState::Paused
+                    if action == Action::QuickSave => self.quick_save(ctx),
+            PlayState::Paused if action == Action::Settings =>
+                self.open_settings(),
+            PlayState::Space => self.title_menu(action),
+            PlayState::Play | PlayState::Countdown => {
+                match action {
+                    Action::Steer(direction) =>
+                        self.held[0].direction = direction,
+                    Action::Throttle(speed) => self.held[0].accelerate = speed,
+                    // the other end can't be stopped to wait, and there's
+                    // no pausing before the off
+                    Action::Pause if self.session.is_some() ||
+                        self.play_state == PlayState::Countdown => {},
+                    Action::Pause => {
+                        self.play_state = PlayState::Paused;
+                        self.pause_selection = 0;
+                        self.held = [Held::new(); 2];
+                    },
+                    _ => {}
+                }
+            },
+            PlayState::Paused => self.pause_menu(ctx, action),
+            PlayState::Settings => self.settings_menu(ctx, action),
+            _ => {}
+        }
+    }
+
+    fn notify(&mut self, ctx: &Context, message: String) {
+        self.notice = Some((message, timer::time_since_start(ctx)));
+    }
+
+    // only a plain single player run can be saved, the other snakes
+    // aren't
+    fn can_quick_save(&mut self, ctx: &Context) -> bool {
+        let rules = &self.config.rules;
+        let alone = !rules.two_player && !rules.rival &&
+            self.second.is_none() && self.session.is_none();
+        if !alone {
+            self.notify(ctx, "quick saves are single player only".to_string());
+        }
+        alone
+    }
+
+    fn quick_save(&mut self, ctx: &mut Context) {
+        if !self.can_quick_save(ctx) {
+            return;
+        }
+        let save = Save {
+            difficulty: self.config.rules.difficulty,
+            snake: self.snake.save(),
+            fruit: self.fruit.iter().map(|f| (f.pos, f.kind, f.n)).collect(),
+            rocks: self.obstacles.rocks.iter().map(|r| r.pos).collect(),
+            level: self.level,
+            score: self.score.score,
+            passengers: self.passengers[0],
+            boarded_at: self.boarded_at[0],
+            boost: self.boost[0],
+            stations: self.stations.iter().map(|s| s.zone.save()).collect(),
+            signals: self.signals.iter()
+                .map(|s| (s.aspect, s.timer, s.zone.save()))
+                .collect(),
+            swirl: self.swirl.clone(),
+            shown_length: self.shown_length,
+            lives: self.lives,
+            combo: self.combos[0],
+            stats: self.stats,
+            graph: self.graph.clone(),
+            chain: self.chain.clone(),
+            chain_countdown: self.chain_countdown,
+            run: self.run.clone(),
+            ghost_step: self.ghost.as_ref().map(|g| g.step())
+        };
+        if let Err(e) = save.write(ctx) {
+            self.notify(ctx, format!("couldn't save: {}", e));
+        }
+    }
+
+    // a loaded game always comes back paused, so there's a moment to get
+    // ready; the random numbers carry on from here rather than from the save
+    fn quick_load(&mut self, ctx: &mut Context) {
+        if !self.can_quick_save(ctx) {
+            return;
+        }
+        let save = match Save::read(ctx) {
+            Ok(save) => save,
+            Err(e) => {
+                self.notify(ctx, format!("couldn't load: {}", e));
+                return;
+            }
+        };
+        let difficulty = self.config.rules.difficulty;
+        if save.difficulty != difficulty {
+            self.notify(ctx, format!("that save is from a game on {}",
+                                     save.difficulty.name()));
+            return;
+        }
+        self.snake.restore(save.snake);
+        let radius = self.fruit_sprites.radius();
+        self.fruit = save.fruit.into_iter()
+            .map(|(pos, kind, n)| Fruit {
+                pos,
+                radius,
+                kind,
+                n,
+                velocity: na::Vector2::new(0.0, 0.0)
+            })
+            .collect();
+        let radius = self.obstacles.radius();
+        self.obstacles.rocks = save.rocks.into_iter()
+            .map(|pos| Rock { pos, radius })
+            .collect();
+        self.level = save.level;
+        self.transition = 0;
+        // the ghost is put back to where it had got to
+        let screen = screen_size(ctx);
+        self.ghost = match (Replay::read(ctx, difficulty), save.ghost_step) {
+            (Ok(replay), Some(step)) =>
+                match Ghost::new(&mut self.image_cache, ctx,
+                                 &self.config.rules, replay) {
+                    Ok(mut ghost) => {
+                        ghost.skip_to(screen, step);
+                        Some(ghost)
+                    },
+                    Err(_) => None
+                },
+            _ => None
+        };
+        self.run = save.run;
+        self.chain = save.chain;
+        self.chain_countdown = save.chain_countdown;
+        self.lives = save.lives;
+        self.combos = [save.combo, Combo::new()];
+        self.popups.clear();
+        self.stats = save.stats;
+        self.graph = save.graph;
+        self.score.score = save.score;
+        self.passengers = [save.passengers, 0];
+        self.boarded_at = [save.boarded_at, None];
+        self.boost = [save.boost, 1.0];
+        for (station, zone) in self.stations.iter_mut().zip(save.stations) {
+            station.zone.restore(zone);
+        }
+        for (signal, (aspect, timer, zone)) in
+                self.signals.iter_mut().zip(save.signals) {
+            signal.aspect = aspect;
+            signal.timer = timer;
+            signal.zone.restore(zone);
+        }
+        self.swirl = save.swirl;
+        self.shown_length = save.shown_length;
+        self.dead_steps = 0;
+        self.explosion.stop();
+        self.restart = false;
+        self.held = [Held::new(); 2];
+        self.pause_selection = 0;
+        self.play_state = PlayState::Paused;
+    }
+
+    fn title_menu(&mut self, action: Action) {
+        let n = TITLE_MENU.len();
+        match action {
+            Action::Up | Action::Throttle(Speed::Accelerate) =>
+                self.title_selection = (self.title_selection + n - 1) % n,
+            Action::Down | Action::Throttle(Speed::Brake) =>
+                self.title_selection = (self.title_selection + 1) % n,
+            _ => self.choose_difficulty(action)
+        }
+    }
+
+    fn title_choice(&mut self, ctx: &mut Context) {
+        self.net_message = None;
+        match self.title_selection {
+            0 => {
+                // every run starts from the same place
+                self.restart = true;
+                self.audio.queue(Sound::Start);
+            },
+            3 => self.play_state = PlayState::Simulation,
+            choice => {
+                let (width, height) = screen_size(ctx);
+                let connecting = if choice == 1 {
+                    let mut rules = self.config.rules.clone();
+                    rules.seed = Some(rules.seed.unwrap_or_else(rand::random));
+                    rules.two_player = true;
+                    let game = net::Game {
+                        rules,
+                        width,
+                        height
+                    };
+                    Connecting::host(self.config.net.port, game)
+                } else {
+                    Connecting::join(&self.config.net.address,
+                                     (width, height))
+                };
+                match connecting {
+                    Ok(connecting) => {
+                        self.connecting = Some(connecting);
+                        self.play_state = PlayState::Connecting;
+                    },
+                    Err(e) => self.net_message = Some(e.to_string())
+                }
+            }
+        }
+    }
+
+    // once both ends are talking they switch to the host's rules for a two
+    // player game, and start together
+    fn poll_connection(&mut self) {
+        let connecting = match &mut self.connecting {
+            Some(connecting) => connecting,
+            None => return
+        };
+        match connecting.poll() {
+            Ok(None) => {},
+            Ok(Some((session, game))) => {
+                self.connecting = None;
+                self.offline_rules = Some(std::mem::replace(
+                    &mut self.config.rules, game.rules));
+                self.session = Some(session);
+                self.held = [Held::new(); 2];
+                self.restart = true;
+                self.audio.queue(Sound::Start);
+            },
+            Err(e) => {
+                self.connecting = None;
+                self.net_message = Some(e.to_string());
+                self.play_state = PlayState::Space;
+            }
+        }
+    }
+
+    // steps only go ahead once the other end's inputs for them are in,
+    // which keeps both simulations the same
+    fn online_step(&mut self, ctx: &mut Context) -> GameResult {
+        let keys = self.held[0];
+        let inputs = match self.session.as_mut().map(|s| s.exchange(keys)) {
+            Some(Ok(Some(inputs))) => inputs,
+            Some(Ok(None)) | None => return Ok(()),
+            Some(Err(e)) => {
+                // once the run's over the other end leaving isn't news
+                let message = if self.play_state == PlayState::Dead {
+                    None
+                } else {
+                    Some(format!("online game ended: {}", e))
+                };
+                return self.end_session(ctx, message);
+            }
+        };
+        self.held = inputs;
+        self.step(ctx)?;
+        self.held[0] = keys;
+        if self.play_state == PlayState::GameOver {
+            self.end_session(ctx, None)?;
+        }
+        Ok(())
+    }
+
+    fn end_session(&mut self, ctx: &mut Context,
+                   message: Option<String>) -> GameResult {
+        self.session = None;
+        if let Some(rules) = self.offline_rules.take() {
+            self.config.rules = rules;
+        }
+        self.net_message = message;
+        self.held = [Held::new(); 2];
+        // a finished game's results stay up until they're dismissed
+        if self.play_state != PlayState::GameOver {
+            self.play_state = PlayState::Space;
+        }
+        self.reset(ctx)
+    }
+
+    // left and right on the title screen pick how hard the next run is,
+    // and it's saved like any other setting
+    fn choose_difficulty(&mut self, action: Action) {
+        let rules = &mut self.config.rules;
+        match action {
+            Action::Left | Action::Steer(Direction::Left) =>
+                rules.difficulty = rules.difficulty.next().next(),
+            Action::Right | Action::Steer(Direction::Right) =>
+                rules.difficulty = rules.difficulty.next(),
+            _ => return
+        }
+        self.config.settings.difficulty = Some(rules.difficulty);
+        if let Err(e) = self.config.save() {
+            warn(format_args!("couldn't save settings: {}", e));
+        }
+    }
+
+    fn save_card(&mut self, ctx: &mut Context) {
+        let screen = screen_size(ctx);
+        let top_speed = self.config.rules.top_speed();
+        if let Some(results) = &self.last_run {
+            match results.save_card(ctx, screen, top_speed) {
+                Ok(path) => self.card_saved = Some(path),
+                Err(e) => warn(format_args!("couldn't save score card: {}",
+                                            e))
+            }
+        }
+    }
+
+    fn open_settings(&mut self) {
+        self.settings.open(self.on_title());
+        self.settings_from = self.play_state;
+        self.play_state = PlayState::Settings;
+    }
+
+    fn settings_menu(&mut self, ctx: &mut Context, action: Action) {
+        match self.settings.act(ctx, &mut self.config, action) {
+            Ok(true) => self.play_state = self.settings_from,
+            Ok(false) => {},
+            Err(e) => warn(format_args!("couldn't change settings: {}", e))
+        }
+        self.audio.volume = self.config.volume;
+        self.music.volume = self.config.volume;
+    }
+
+    fn release(&mut self, action: Action) {
+        match action {
+            Action::Steer(_) => self.held[0].direction = Direction::Straight,
+            Action::Throttle(_) => self.held[0].accelerate = Speed::Coast,
+            _ => {}
+        }
+    }
+
+    // in the middle of a local two player game the arrow keys steer the second
+    // snake rather than working the menus
+    fn second_action(&self, keycode: keyboard::KeyCode) -> Option<Action> {
+        let playing = self.play_state == PlayState::Play ||
+            self.play_state == PlayState::Countdown;
+        if self.second.is_none() || self.session.is_some() || !playing {
+            return None;
+        }
+        match self.second_keys.action(keycode) {
+            Some(action @ (Action::Steer(_) | Action::Throttle(_))) =>
+                Some(action),
+            _ => None
+        }
+    }
+
+    fn hold_second(&mut self, action: Action, pressed: bool) {
+        let held = &mut self.held[1];
+        match (action, pressed) {
+            (Action::Steer(direction), true) => held.direction = direction,
+            (Action::Steer(_), false) => held.direction = Direction::Straight,
+            (Action::Throttle(speed), true) => held.accelerate = speed,
+            (Action::Throttle(_), false) => held.accelerate = Speed::Coast,
+            _ => {}
+        }
+    }
+
+    fn pause_menu(&mut self, ctx: &mut Context, action: Action) {
+        let n = PAUSE_MENU.len();
+        match action {
+            Action::Up | Action::Throttle(Speed::Accelerate) =>
+                self.pause_selection = (self.pause_selection + n - 1) % n,
+            Action::Down | Action::Throttle(Speed::Brake) =>
+                self.pause_selection = (self.pause_selection + 1) % n,
+            Action::Pause => self.play_state = PlayState::Play,
+            Action::Confirm => {
+                match self.pause_selection {
+                    0 => self.play_state = PlayState::Play,
+                    1 => {
+                        self.restart = true;
+                        self.audio.queue(Sound::Start);
+                    },
+                    2 => self.open_settings(),
+                    _ => event::quit(ctx)
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn draw_pause_menu(&mut self, ctx: &mut Context) -> GameResult {
+        let options: Vec<String> =
+            PAUSE_MENU.iter().map(|o| o.to_string()).collect();
+        draw_menu(ctx, &options, self.pause_selection)
+    }
+
+    // pull up at a station for a second to drop off the passengers
+    // from the last one and pick up a fresh load
+    fn stop_at_stations(&mut self) {
+        let mut heads = [(self.snake.head.pos, self.snake.head.speed); 2];
+        if let Some(second) = &self.second {
+            heads[1] = (second.head.pos, second.head.speed);
+        }
+        let players = if self.second.is_some() { 2 } else { 1 };
+        for (player, &(pos, speed)) in heads.iter().enumerate()
+                .take(players) {
+            for (i, station) in self.stations.iter_mut().enumerate() {
+                if !station.zone.dwell(player, pos, speed,
+                                       UPDATE_RATE as i32) ||
+                        self.boarded_at[player] == Some(i) {
+                    continue;
+                }
+                let fares = self.passengers[player] * 10;
+                if player == 0 {
+                    self.score.score += fares;
+                } else {
+                    self.second_score += fares;
+                }
+                self.passengers[player] = 5;
+                self.boarded_at[player] = Some(i);
+            }
+        }
+    }
+
+    // length, speed and cargo alongside the score digits
+    fn draw_hud(&mut self, ctx: &mut Context) -> GameResult {
+        if self.on_title() {
+            return Ok(());
+        }
+        let line = &mut self.hud;
+        line.clear();
+        line.push(format_args!("{}   length {:.0}   speed {:.1}",
+                               self.config.rules.difficulty.name(),
+                               self.snake.current_length,
+                               self.snake.head.speed));
+        if self.passengers[0] > 0 {
+            line.push(format_args!("   passengers {}", self.passengers[0]));
+        }
+        if self.second.is_none() && self.config.rules.lives > 1 {
+            line.push(format_args!("   lives {}", self.lives));
+        }
+        if let Some(level) = level::nth(&self.levels, self.level) {
+            line.push(format_args!("   level {} {}", self.level + 1,
+                                   level.name));
+        }
+        if let Some(chain) = &self.chain {
+            line.push(format_args!("   {}", chain));
+        }
+        if self.second.is_some() {
+            line.push(format_args!("   P2 {}", self.second_score));
+            if self.combos[1].multiplier > 1 {
+                line.push(format_args!(" \u{d7}{}",
+                                       self.combos[1].multiplier));
+            }
+        }
+        if let (Some(_), Some(target)) = (&self.hill, self.config.rules.hill) {
+            line.push(format_args!("   first to {}", target));
+        }
+        graphics::draw(ctx, line.text(),
+            graphics::DrawParam::new().dest(na::Point2::new(20.0, 20.0)))?;
+        let mut y = 52.0;
+        if self.config.rules.boost {
+            self.draw_boost(ctx, 0, y)?;
+            y += 20.0;
+            if self.second.is_some() {
+                self.draw_boost(ctx, 1, y)?;
+                y += 20.0;
+            }
+        }
+        self.draw_length_bar(ctx, y)?;
+        self.draw_combo(ctx)
+    }
+
+    // big under the score, with what's left of the window to keep it going
+    fn draw_combo(&mut self, ctx: &mut Context) -> GameResult {
+        let combo = &self.combos[0];
+        if combo.multiplier <= 1 {
+            return Ok(());
+        }
+        let (w, _) = screen_size(ctx);
+        let gold = graphics::Color::new(1.0, 0.85, 0.3, 1.0);
+        self.combo_label.clear();
+        self.combo_label.push(format_args!("\u{d7}{}", combo.multiplier));
+        let text = self.combo_label.text();
+        let (tw, th) = text.dimensions(ctx);
+        let x = w - tw as f32 - 20.0;
+        graphics::draw(ctx, text, graphics::DrawParam::new()
+            .dest(na::Point2::new(x, 90.0)).color(gold))?;
+        let bar = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(x, 94.0 + th as f32,
+                                (tw as f32 * combo.left()).max(1.0), 6.0),
+            gold)?;
+        graphics::draw(ctx, &bar, graphics::DrawParam::new())
+    }
+
+    // progress towards the next milestone, with a thin line underneath for
+    // how far it is to the longest a snake can get
+    fn draw_length_bar(&mut self, ctx: &mut Context, y: f32) -> GameResult {
+        let length = self.shown_length;
+        let milestone = ((length / LENGTH_MILESTONE).floor() + 1.0)
+            * LENGTH_MILESTONE;
+        let progress = (length - (milestone - LENGTH_MILESTONE))
+            / LENGTH_MILESTONE;
+        let outline = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::stroke(2.0),
+            graphics::Rect::new(20.0, y, 200.0, 12.0),
+            graphics::WHITE)?;
+        graphics::draw(ctx, &outline, graphics::DrawParam::new())?;
+        if progress > 0.0 {
+            let fill = graphics::Mesh::new_rectangle(ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(20.0, y, 200.0 * progress, 12.0),
+                graphics::Color::new(0.3, 0.9, 0.4, 1.0))?;
+            graphics::draw(ctx, &fill, graphics::DrawParam::new())?;
+        }
+        let cap = (length / snake::MAX_LENGTH).min(1.0);
+        if cap > 0.0 {
+            let line = graphics::Mesh::new_rectangle(ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(20.0, y + 15.0, 200.0 * cap, 3.0),
+                graphics::Color::new(0.3, 0.9, 0.4, 0.6))?;
+            graphics::draw(ctx, &line, graphics::DrawParam::new())?;
+        }
+        self.milestone_label.clear();
+        self.milestone_label.push(format_args!("{:.0}", milestone));
+        graphics::draw(ctx, self.milestone_label.text(),
+                       graphics::DrawParam::new()
+            .dest(na::Point2::new(228.0, y - 2.0)))
+    }
+
+    // the title image with the difficulty and menu underneath, or what's
+    // happening with an online game
+    fn draw_title(&mut self, ctx: &mut Context) -> GameResult {
+        let (w, h) = screen_size(ctx);
+        graphics::draw(ctx,
+            &self.space_image,
+            graphics::DrawParam::new()
+                .offset(na::Point2::new(0.5, 0.5))
+                .dest(na::Point2::new(w / 2.0, h / 2.0))
+        )?;
+        let mut lines = Vec::<(String, f32, bool)>::new();
+        if let Some(connecting) = &self.connecting {
+            let status = match connecting {
+                Connecting::Hosting(..) => format!(
+                    "waiting for a player on port {}", self.config.net.port),
+                Connecting::Joining(..) => format!(
+                    "joining {}", self.config.net.address)
+            };
+            lines.push((status, 28.0, false));
+            lines.push(("Space to cancel".to_string(), 20.0, false));
+        } else {
+            lines.push((format!("<  {}  >",
+                                self.config.rules.difficulty.name()),
+                        32.0, false));
+            for (i, option) in TITLE_MENU.iter().enumerate() {
+                lines.push((option.to_string(), 24.0,
+                            i == self.title_selection));
+            }
+            if let Some(message) = &self.net_message {
+                lines.push((message.clone(), 20.0, false));
+            }
+        }
+
+        let mut y = h / 2.0 + self.space_image.height() as f32 / 2.0 + 20.0;
+        for (line, size, selected) in lines {
+            let text = graphics::Text::new(
+                (line, graphics::Font::default(), size));
+            let (tw, _) = text.dimensions(ctx);
+            let color = if selected {
+                graphics::Color::new(1.0, 0.85, 0.3, 1.0)
+            } else {
+                graphics::WHITE
+            };
+            graphics::draw(ctx, &text, graphics::DrawParam::new()
+                .dest(na::Point2::new((w - tw as f32) / 2.0, y))
+                .color(color))?;
+            y += size + 12.0;
+        }
+        Ok(())
+    }
+
+    // the rocks and walls as they'd be for a run, with the scores on top
+    fn draw_simulation(&mut self, ctx: &mut Context) -> GameResult {
+        if self.walled() {
+            graphics::draw(ctx, &self.walls, graphics::DrawParam::new())?;
+        }
+        self.obstacles.draw(ctx, &self.config.display)?;
+        if let Some(simulation) = &mut self.simulation {
+            simulation.draw(ctx, &self.config.display,
+                            &mut self.fruit_sprites, 1.0)?;
+            simulation.draw_scoreboard(ctx)?;
+        }
+        Ok(())
+    }
+
+    fn draw_countdown(&mut self, ctx: &mut Context) -> GameResult {
+        let (w, h) = screen_size(ctx);
+        let n = (self.countdown - 1) / UPDATE_RATE as i32 + 1;
+        let text = graphics::Text::new(
+            (n.to_string(), graphics::Font::default(), 160.0));
+        let (tw, th) = text.dimensions(ctx);
+        graphics::draw(ctx, &text, graphics::DrawParam::new()
+            .dest(na::Point2::new((w - tw as f32) / 2.0,
+                                  (h - th as f32) / 2.0)))
+    }
+
+    fn draw_game_over(&mut self, ctx: &mut Context) -> GameResult {
+        let results = match &self.last_run {
+            Some(results) => results,
+            None => return Ok(())
+        };
+        let (w, h) = screen_size(ctx);
+        let dim = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, w, h),
+            graphics::Color::new(0.0, 0.0, 0.0, 0.6))?;
+        graphics::draw(ctx, &dim, graphics::DrawParam::new())?;
+
+        let stats = &results.stats;
+        let mut lines = vec![("game over".to_string(), 48.0)];
+        if let Some(verdict) = &results.verdict {
+            lines.push((verdict.clone(), 28.0));
+        }
+        lines.extend(vec![
+            (format!("score {}", results.score), 32.0),
+            (format!("longest {:.0}", stats.longest), 24.0),
+            (format!("top speed {:.1}", stats.fastest), 24.0),
+            (format!("time {}", stats.duration()), 24.0),
+            (format!("fruit eaten {}", stats.fruit), 24.0),
+            ("Space to continue".to_string(), 20.0),
+        ]);
+        let mut y = h / 12.0;
+        for (line, size) in lines {
+            let text = graphics::Text::new(
+                (line, graphics::Font::default(), size));
+            let (tw, _) = text.dimensions(ctx);
+            graphics::draw(ctx, &text, graphics::DrawParam::new()
+                .dest(na::Point2::new((w - tw as f32) / 2.0, y)))?;
+            y += size + 8.0;
+        }
+        Ok(())
+    }
+
+    fn draw_level_complete(&mut self, ctx: &mut Context) -> GameResult {
+        let (w, h) = screen_size(ctx);
+        let next = match level::nth(&self.levels, self.level + 1) {
+            Some(next) => next,
+            None => return Ok(())
+        };
+        let lines = [format!("level {} complete", self.level + 1),
+                     format!("next: {}  ({} to reach)", next.name,
+                             level::target(&self.levels, self.level + 1))];
+        for (i, line) in lines.iter().enumerate() {
+            let size = if i == 0 { 48.0 } else { 28.0 };
+            let text = graphics::Text::new(
+                (line.as_str(), graphics::Font::default(), size));
+            let (tw, _) = text.dimensions(ctx);
+            graphics::draw(ctx, &text, graphics::DrawParam::new()
+                .dest(na::Point2::new((w - tw as f32) / 2.0,
+                                      h / 2.0 - 60.0 + i as f32 * 70.0)))?;
+        }
+        Ok(())
+    }
+
+    // with the boost rules accelerating only works while there's something
+    // in the meter, and coasting tops it back up
+    fn throttle(&mut self, player: usize) -> Speed {
+        let rules = &self.config.rules;
+        if !rules.boost {
+            return self.held[player].accelerate;
+        }
+        let dt = 1.0 / UPDATE_RATE as f32;
+        let boost = &mut self.boost[player];
+        match self.held[player].accelerate {
+            Speed::Accelerate if *boost > 0.0 => {
+                *boost = (*boost - rules.boost_drain * dt).max(0.0);
+                Speed::Accelerate
+            },
+            Speed::Accelerate => Speed::Coast,
+            Speed::Coast => {
+                *boost = (*boost + rules.boost_refill * dt).min(1.0);
+                Speed::Coast
+            },
+            Speed::Brake => Speed::Brake
+        }
+    }
+
+    fn draw_boost(&mut self, ctx: &mut Context, player: usize,
+                  y: f32) -> GameResult {
+        let outline = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::stroke(2.0),
+            graphics::Rect::new(20.0, y, 200.0, 12.0),
+            graphics::WHITE)?;
+        graphics::draw(ctx, &outline, graphics::DrawParam::new())?;
+        let boost = self.boost[player];
+        if boost > 0.0 {
+            let fill = graphics::Mesh::new_rectangle(ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(20.0, y, 200.0 * boost, 12.0),
+                graphics::Color::new(1.0, 0.8, 0.2, 1.0))?;
+            graphics::draw(ctx, &fill, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    // one fixed step of the simulation
+    fn step(&mut self, ctx: &mut Context) -> GameResult {
+        profile!("update");
+        if self.restart {
+            self.restart = false;
+            self.reset(ctx)?;
+            self.play_state = PlayState::Countdown;
+            self.countdown = COUNTDOWN;
+        }
+
+        self.update_simulation(ctx)?;
+        if self.play_state == PlayState::Paused ||
+                self.play_state == PlayState::Settings ||
+                self.play_state == PlayState::Simulation {
+            return Ok(());
+        }
+
+        // whatever's held when it runs out is what the snake sets off with
+        if self.play_state == PlayState::Countdown {
+            self.countdown -= 1;
+            if self.countdown <= 0 {
+                self.play_state = PlayState::Play;
+            }
+            return Ok(());
+        }
+
+        if self.play_state == PlayState::LevelComplete {
+            self.transition -= 1;
+            if self.transition <= 0 {
+                self.level += 1;
+                self.start_level(ctx)?;
+                self.play_state = PlayState::Play;
+            }
+            return Ok(());
+        }
+
+        let (w, h) = screen_size(ctx);
+        let dt = 1.0 / UPDATE_RATE as f32;
+        let accelerate = self.throttle(0);
+        self.snake.update((w, h), &self.held[0].direction, &accelerate,
+                          &self.config.rules, dt);
+        self.speed_lines.update(self.play_state == PlayState::Play &&
+            self.snake.head.speed >= self.config.rules.top_speed() - 0.01);
+        // most of the way there in a quarter of a second
+        self.shown_length += (self.snake.current_length - self.shown_length)
+            * (8.0 * dt).min(1.0);
+        if self.play_state == PlayState::Play {
+            self.graph.record(self.snake.head.speed,
+                              self.snake.current_length,
+                              self.snake.head.pos);
+            self.stats.record(self.snake.head.speed,
+                              self.snake.current_length);
+            if let Some(run) = &mut self.run {
+                run.record(&self.snake);
+            }
+            if let Some(ghost) = &mut self.ghost {
+                ghost.update((w, h));
+            }
+        }
+
+        // on the stack, as this runs every step
+        let heads = [self.snake.head.pos,
+                     self.second.as_ref().map_or(self.snake.head.pos,
+                                                 |s| s.head.pos)];
+        let heads = &heads[..if self.second.is_some() { 2 } else { 1 }];
+        self.swirl.update();
+        let pull = if self.swirl.active() {
+            heads.get(self.swirl.player()).copied()
+        } else {
+            None
+        };
+        for fruit in self.fruit.iter_mut() {
+            fruit.update(heads, pull, w, h, dt);
+        }
+
+        // each fruit eaten is replaced on its own
+        let mut eaten = Vec::<(fruit::Kind, na::Point2<f32>)>::new();
+        let score = self.score.score;
+        for i in 0..self.fruit.len() {
+            if self.snake.collide(&self.fruit[i].pos, self.fruit[i].radius) {
+                eaten.push((self.fruit[i].kind, self.fruit[i].pos));
+                self.fruit[i] = self.place_fruit(w, h, score);
+            }
+        }
+        if self.play_state == PlayState::Play {
+            self.update_chain(w, h);
+        }
+        if self.play_state == PlayState::Play && self.config.rules.rival {
+            self.update_rival(ctx)?;
+        }
+        if self.play_state == PlayState::Play {
+            if let Some(second) = &mut self.second {
+                if let Some(tail) = second.cut_by(&self.snake) {
+                    self.cut(1, tail);
+                }
+            }
+            self.update_debris(0);
+        }
+        let second_crashed = self.play_state == PlayState::Play &&
+            self.update_second(w, h, dt);
+        let crashed = self.obstacles.rocks.iter()
+            .any(|r| self.snake.collide(&r.pos, r.radius)) ||
+            (self.walled() && self.snake.wrapped()) ||
+            self.rival.as_ref().is_some_and(|r| self.snake.hits(&r.snake)) ||
+            self.second.as_ref().is_some_and(|s| self.snake.hits_head(s));
+        let poisoned = eaten.iter().any(|e| e.0 == fruit::Kind::Poison);
+        if self.play_state == PlayState::Play && !poisoned {
+            for (kind, pos) in eaten {
+                self.eat(0, kind, pos);
+            }
+        }
+
+        for signal in self.signals.iter_mut() {
+            signal.update();
+        }
+
+        if self.play_state == PlayState::Play {
+            self.stop_at_stations();
+            self.check_signals();
+            for combo in self.combos.iter_mut() {
+                combo.update();
+            }
+        }
+        let reduced_motion = self.config.display.reduced_motion;
+        self.popups.retain_mut(|p| p.update(reduced_motion));
+
+        if self.play_state == PlayState::Play &&
+                self.snake.near_miss(&self.config.rules) {
+            self.snake.flash.trigger(graphics::Color::new(1.0, 0.2, 0.2, 1.0),
+                                     0.2);
+        }
+
+        let first_crashed = poisoned || crashed ||
+            self.snake.collide_self(&self.config.rules);
+        if self.play_state == PlayState::Play &&
+                (first_crashed || second_crashed) {
+            self.play_state = PlayState::Dead;
+            // both going at once, head on or otherwise, is a draw
+            self.loser = match (first_crashed, second_crashed) {
+                (true, true) => None,
+                (true, false) => Some(0),
+                _ => Some(1)
+            };
+            if self.second.is_none() {
+                self.lives -= 1;
+            }
+            if !self.respawning() {
+                let verdict = self.second.as_ref().map(|_| self.verdict());
+                self.last_run = Some(self.results(verdict));
+                self.card_saved = None;
+                self.keep_best(ctx);
+            }
+            self.audio.queue(Sound::Crash);
+            self.dead_steps = DEAD_STEPS;
+            let first = match self.loser {
+                Some(1) => None,
+                _ => self.snake.segments()
+            };
+            let second = match (self.loser, &mut self.second) {
+                (Some(0), _) | (_, None) => None,
+                (_, Some(second)) => second.segments()
+            };
+            let segments = first.into_iter().flatten()
+                .chain(second.into_iter().flatten());
+            self.explosion.start(segments, &mut self.rng);
+        }
+
+        if self.play_state == PlayState::Play {
+            if let Some(winner) = self.update_hill(w, h, dt) {
+                self.take_hill(ctx, winner)?;
+            }
+        }
+
+        if self.play_state == PlayState::Play && self.level_complete() {
+            self.play_state = PlayState::LevelComplete;
+            self.transition = 2 * UPDATE_RATE as i32;
+            self.held = [Held::new(); 2];
+            self.audio.queue(Sound::Start);
+        }
+
+        if self.play_state == PlayState::Dead {
+            self.dead_steps -= 1;
+        }
+        if self.play_state == PlayState::Dead && self.dead_steps <= 0 {
+            if self.respawning() {
+                self.respawn(ctx)?;
+            } else {
+                self.play_state = PlayState::GameOver;
+                self.reset(ctx)?;
+            }
+        }
+
+        self.explosion.update(ctx, &mut self.audio)?;
+
+        Ok(())
+    }
+
+    // fast forward while it's being watched, and at normal speed behind
+    // the title screen; it starts afresh each time
+    fn update_simulation(&mut self, ctx: &mut Context) -> GameResult {
+        let steps = match self.play_state {
+            PlayState::Simulation => sim::SPEED_UP,
+            PlayState::Space if self.config.display.attract => 1,
+            _ => {
+                self.simulation = None;
+                return Ok(());
+            }
+        };
+        let mut simulation = match self.simulation.take() {
+            Some(simulation) => simulation,
+            None => Simulation::new(&self.config.rules, self.walled(),
+                                    &self.fruit_sprites, &self.obstacles,
+                                    screen_size(ctx))
+        };
+        for _ in 0..steps {
+            simulation.step(&mut self.image_cache, ctx, &self.fruit_sprites,
+                            &self.obstacles)?;
+        }
+        self.simulation = Some(simulation);
+        Ok(())
+    }
+
+    // a crash with lives to spare in a single player run
+    // along the bottom for a couple of seconds, over whatever else is up
+    fn draw_notice(&mut self, ctx: &mut Context) -> GameResult {
+        let message = match &self.notice {
+            Some((message, since)) if timer::time_since_start(ctx) - *since <
+                    time::Duration::from_secs(2) => message,
+            _ => return Ok(())
+        };
+        let (w, h) = screen_size(ctx);
+        let text = graphics::Text::new(
+            (message.as_str(), graphics::Font::default(), 24.0));
+        let (tw, _) = text.dimensions(ctx);
+        graphics::draw(ctx, &text, graphics::DrawParam::new()
+            .dest(na::Point2::new((w - tw as f32) / 2.0, h - 80.0)))
+    }
+
+    // who crashed in a two player game
+    fn verdict(&self) -> String {
+        match self.loser {
+            Some(loser) => format!("player {} crashed", loser + 1),
+            None => "both crashed, it's a draw".to_string()
+        }
+    }
+
+    fn respawning(&self) -> bool {
+        self.second.is_none() && self.lives > 0
+    }
+
+    // a fresh snake at half the length somewhere clear, with the score,
+    // fruit and everything else carrying on; there's a countdown to get
+    // ready again
+    fn respawn(&mut self, ctx: &mut Context) -> GameResult {
+        let (w, h) = screen_size(ctx);
+        let length = self.snake.desired_length() / 2.0;
+        let (pos, angle) = self.safe_spot(w, h);
+        let rules = &self.config.rules;
+        self.snake = Snake::spawn(&mut self.image_cache, ctx, rules,
+                                  "/train00.png", pos, angle)?;
+        self.snake.increase_length(length - rules.start_length.min(length));
+        self.explosion.stop();
+        self.dead_steps = 0;
+        self.combos[0] = Combo::new();
+        self.held = [Held::new(); 2];
+        self.play_state = PlayState::Countdown;
+        self.countdown = COUNTDOWN;
+        Ok(())
+    }
+
+    // away from the edges and not too near a rock or the rival, falling
+    // back on the middle if nowhere turns up quickly
+    fn safe_spot(&mut self, w: f32, h: f32) -> (na::Point2<f32>, f32) {
+        let clearance = 150.0;
+        for _ in 0..20 {
+            let pos = na::Point2::new(self.rng.gen_range(w * 0.2..w * 0.8),
+                                      self.rng.gen_range(h * 0.2..h * 0.8));
+            let angle = self.rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+            let near_rival = self.rival.as_ref().is_some_and(|r|
+                na::distance(&r.snake.head.pos, &pos) < clearance);
+            if !self.obstacles.hit(&pos, clearance) && !near_rival {
+                return (pos, angle);
+            }
+        }
+        (na::Point2::new(w / 2.0, h / 2.0), 0.0)
+    }
+
+    fn results(&mut self, verdict: Option<String>) -> Results {
+        self.graph.finish(self.snake.head.speed, self.snake.current_length,
+                          self.snake.head.pos);
+        let mut mode = self.config.rules.difficulty.name().to_string();
+        if self.second.is_some() {
+            mode += ", two player";
+        }
+        if let Some(level) = level::nth(&self.levels, self.level) {
+            mode += &format!(", level {} {}", self.level + 1, level.name);
+        }
+        Results {
+            graph: std::mem::take(&mut self.graph),
+            stats: self.stats,
+            score: self.score.score,
+            length: self.snake.current_length,
+            mode,
+            seed: self.config.rules.seed,
+            verdict
+        }
+    }
+
+    fn update_music(&mut self) {
+        let track = match (self.play_state, self.settings_from) {
+            (PlayState::Space, _) | (PlayState::Connecting, _) |
+                (PlayState::Simulation, _) |
+                (PlayState::GameOver, _) |
+                (PlayState::Settings, PlayState::Space) => Track::Title,
+            _ => Track::Game
+        };
+        let intensity = self.snake.head.speed / self.config.rules.top_speed();
+        self.music.update(track, intensity);
+    }
+
+    // somewhere that isn't under a rock, and further from the head the
+    // longer the snake, if one can be found quickly
+    fn place_fruit(&mut self, w: f32, h: f32, score: i32) -> Fruit {
+        let distance =
+            difficulty::fruit_distance(self.snake.desired_length());
+        let head = self.snake.head.pos;
+        let mut fruit = Fruit::new(&self.fruit_sprites, &mut self.rng,
+                                   w, h, score);
+        for _ in 0..10 {
+            if !self.obstacles.hit(&fruit.pos, fruit.radius) &&
+                    na::distance(&fruit.pos, &head) >= distance {
+                break;
+            }
+            fruit = Fruit::new(&self.fruit_sprites, &mut self.rng,
+                               w, h, score);
+        }
+        fruit
+    }
+
+    // `player` is 0 for the first player and 1 for the second, and `pos`
+    // is where the fruit was
+    fn eat(&mut self, player: usize, kind: fruit::Kind,
+           pos: na::Point2<f32>) {
+        let growth = self.config.rules.fruit_growth();
+        let snake = match (player, &mut self.second) {
+            (1, Some(second)) => second,
+            _ => &mut self.snake
+        };
+        let mut points = 0;
+        match kind {
+            fruit::Kind::Normal => snake.increase_length(growth),
+            fruit::Kind::Bonus => {
+                snake.increase_length(growth);
+                points += 50;
+            },
+            fruit::Kind::Shrink => snake.increase_length(-growth),
+            fruit::Kind::SpeedBoost => snake.overdrive(3.0),
+            fruit::Kind::Skittish => {
+                snake.increase_length(growth);
+                points += 100;
+            },
+            fruit::Kind::Gravity => self.swirl.trigger(4.0, player),
+            // ends the run rather than being eaten
+            fruit::Kind::Poison => {}
+        }
+        // reward grabbing fruit on the move
+        points += 10 + (snake.head.speed * 5.0) as i32;
+        let multiplier = self.combos[player].eat();
+        self.popups.add(Popup::new(points, multiplier, pos));
+        points *= multiplier;
+        self.audio.queue(Sound::Pickup);
+        self.boost[player] =
+            (self.boost[player] + self.config.rules.boost_fruit).min(1.0);
+        if player == 0 {
+            self.score.score += points;
+            self.stats.fruit += 1;
+        } else {
+            self.second_score += points;
+        }
+    }
+
+    // every so often a chain of numbered fruit turns up alongside the
+    // usual ones, each counting as a normal fruit when it's eaten in turn
+    fn update_chain(&mut self, w: f32, h: f32) {
+        let mut chain = match self.chain.take() {
+            Some(chain) => chain,
+            None => {
+                self.chain_countdown -= 1;
+                if self.chain_countdown <= 0 {
+                    let score = self.score.score;
+                    let fruit = (0..chain::LENGTH)
+                        .map(|_| Fruit {
+                            kind: fruit::Kind::Normal,
+                            n: 0,
+                            ..self.place_fruit(w, h, score)
+                        })
+                        .collect();
+                    self.chain = Some(Chain::new(fruit));
+                }
+                return;
+            }
+        };
+        // either player can eat the next one, or break it for both
+        let mut player = 0;
+        let mut outcome = chain.eat(&self.snake);
+        if outcome.is_none() {
+            if let Some(second) = &self.second {
+                player = 1;
+                outcome = chain.eat(second);
+            }
+        }
+        let in_time = chain.update();
+        let snake = match (player, &mut self.second) {
+            (1, Some(second)) => second,
+            _ => &mut self.snake
+        };
+        // the fruit's right under the head
+        let head = snake.head.pos;
+        let ended = match outcome {
+            Some(Outcome::Next) => {
+                self.eat(player, fruit::Kind::Normal, head);
+                !in_time
+            },
+            Some(Outcome::Complete) => {
+                snake.flash.trigger(
+                    graphics::Color::new(1.0, 0.85, 0.3, 1.0), 0.5);
+                self.eat(player, fruit::Kind::Normal, head);
+                if player == 0 {
+                    self.score.score += chain::BONUS;
+                } else {
+                    self.second_score += chain::BONUS;
+                }
+                true
+            },
+            Some(Outcome::Broken) => {
+                self.audio.queue(Sound::Pop);
+                true
+            },
+            None => !in_time
+        };
+        if ended {
+            self.chain_countdown = CHAIN_EVERY;
+        } else {
+            self.chain = Some(chain);
+        }
+    }
+
+    // the second player eats and crashes just like the first; true on a
+    // crash
+    fn update_second(&mut self, w: f32, h: f32, dt: f32) -> bool {
+        let mut second = match self.second.take() {
+            Some(second) => second,
+            None => return false
+        };
+        let accelerate = self.throttle(1);
+        let rules = &self.config.rules;
+        let direction = self.held[1].direction;
+        second.update((w, h), &direction, &accelerate, rules, dt);
+        let mut eaten = Vec::<(fruit::Kind, na::Point2<f32>)>::new();
+        let score = self.second_score;
+        for i in 0..self.fruit.len() {
+            if second.collide(&self.fruit[i].pos, self.fruit[i].radius) {
+                eaten.push((self.fruit[i].kind, self.fruit[i].pos));
+                self.fruit[i] = self.place_fruit(w, h, score);
+            }
+        }
+        let rules = &self.config.rules;
+        let crashed = eaten.iter().any(|e| e.0 == fruit::Kind::Poison) ||
+            second.collide_self(rules) ||
+            second.hits_head(&self.snake) ||
+            self.obstacles.rocks.iter()
+                .any(|r| second.collide(&r.pos, r.radius)) ||
+            (self.walled() && second.wrapped()) ||
+            self.rival.as_ref().is_some_and(|r| second.hits(&r.snake));
+        let cut = self.snake.cut_by(&second);
+        self.second = Some(second);
+        if let Some(tail) = cut {
+            self.cut(0, tail);
+        }
+        if !crashed {
+            for (kind, pos) in eaten {
+                self.eat(1, kind, pos);
+            }
+            self.update_debris(1);
+        }
+        crashed
+    }
+
+    // points for holding the hill, and which player's reached the target
+    // if either has
+    fn update_hill(&mut self, w: f32, h: f32, dt: f32) -> Option<usize> {
+        let (hill, second) = match (&mut self.hill, &self.second) {
+            (Some(hill), Some(second)) => (hill, second),
+            _ => return None
+        };
+        let heads = [self.snake.head.pos, second.head.pos];
+        let points = hill.update(&mut self.rng, w, h, heads, dt);
+        self.score.score += points[0];
+        self.second_score += points[1];
+        let target = self.config.rules.hill?;
+        if self.score.score >= target {
+            Some(0)
+        } else if self.second_score >= target {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    // the match ends there and then, nobody has to crash
+    fn take_hill(&mut self, ctx: &mut Context, winner: usize) -> GameResult {
+        let verdict = format!("player {} took the hill", winner + 1);
+        self.last_run = Some(self.results(Some(verdict)));
+        self.card_saved = None;
+        self.audio.queue(Sound::Start);
+        self.play_state = PlayState::GameOver;
+        self.reset(ctx)
+    }
+
+    // in a two player game a nose crossing the other snake's body cuts it
+    // there rather than crashing; `victim` carries on shorter and the cut
+    // off tail is left lying for either of them to eat
+    fn cut(&mut self, victim: usize, tail: VecDeque<Segment>) {
+        let snake = match (victim, &mut self.second) {
+            (1, Some(second)) => second,
+            _ => &mut self.snake
+        };
+        snake.flash.trigger(graphics::Color::new(1.0, 0.2, 0.2, 1.0), 0.3);
+        self.audio.queue(Sound::Pop);
+        self.debris.push(Debris::new(tail));
+    }
+
+    // `player` eats any debris under their nose; the first player's turn
+    // also clears away what's been lying about too long
+    fn update_debris(&mut self, player: usize) {
+        let snake = match (player, &self.second) {
+            (1, Some(second)) => second,
+            _ => &self.snake
+        };
+        let pieces: i32 = self.debris.iter_mut().map(|d| d.eat(snake)).sum();
+        if pieces > 0 {
+            self.audio.queue(Sound::Pickup);
+        }
+        if player == 0 {
+            self.score.score += pieces * debris::POINTS;
+            self.debris.retain_mut(|d| d.update());
+        } else {
+            self.second_score += pieces * debris::POINTS;
+        }
+    }
+
+    // the rival chases whatever fruit's nearest it that isn't poison, and
+    // crashes into the same things the player does
+    fn update_rival(&mut self, ctx: &mut Context) -> GameResult {
+        let mut rival = match self.rival.take() {
+            Some(rival) => rival,
+            None => {
+                self.rival_respawn -= 1;
+                if self.rival_respawn <= 0 {
+                    self.rival = Some(self.spawn_rival(ctx)?);
+                }
+                return Ok(());
+            }
+        };
+        let (w, h) = screen_size(ctx);
+        let dt = 1.0 / UPDATE_RATE as f32;
+        let head = rival.snake.head.pos;
+        let target = self.fruit.iter()
+            .filter(|f| f.kind != fruit::Kind::Poison)
+            .map(|f| f.pos)
+            .min_by(|a, b| na::distance_squared(a, &head)
+                .partial_cmp(&na::distance_squared(b, &head)).unwrap());
+        rival.update((w, h), target, &self.config.rules, dt);
+
+        let mut crashed = false;
+        let score = self.score.score;
+        for i in 0..self.fruit.len() {
+            let fruit = &self.fruit[i];
+            if rival.snake.collide(&fruit.pos, fruit.radius) {
+                crashed |= fruit.kind == fruit::Kind::Poison;
+                rival.snake.increase_length(self.config.rules.fruit_growth());
+                self.fruit[i] = self.place_fruit(w, h, score);
+            }
+        }
+        crashed |= rival.snake.collide_self(&self.config.rules) ||
+            rival.snake.hits(&self.snake) ||
+            self.second.as_ref().is_some_and(|s| rival.snake.hits(s)) ||
+            self.obstacles.rocks.iter()
+                .any(|r| rival.snake.collide(&r.pos, r.radius)) ||
+            (self.walled() && rival.snake.wrapped());
+        if crashed {
+            self.audio.queue(Sound::Pop);
+            self.rival_respawn = RIVAL_RESPAWN;
+        } else {
+            self.rival = Some(rival);
+        }
+        Ok(())
+    }
+
+    // in whichever clear corner is furthest from the player, facing the
+    // middle of the screen
+    fn spawn_rival(&mut self, ctx: &mut Context) -> GameResult<Rival> {
+        let (w, h) = screen_size(ctx);
+        let head = self.snake.head.pos;
+        let corners: Vec<na::Point2<f32>> =
+            [(0.15, 0.15), (0.85, 0.15), (0.15, 0.85), (0.85, 0.85)].iter()
+            .map(|(x, y)| na::Point2::new(x * w, y * h))
+            .collect();
+        let pos = corners.iter()
+            .copied()
+            .filter(|p| !self.obstacles.hit(p, 60.0))
+            .max_by(|a, b| na::distance_squared(a, &head)
+                .partial_cmp(&na::distance_squared(b, &head)).unwrap())
+            .unwrap_or(corners[0]);
+        let to = na::Point2::new(w / 2.0, h / 2.0) - pos;
+        Rival::new(&mut self.image_cache, ctx, &self.config.rules, pos,
+                   (-to.y).atan2(-to.x))
+    }
+
+    // what a snake with its head at `head` keeps its eyes on
+    fn nearest_fruit(&self, head: na::Point2<f32>) -> na::Point2<f32> {
+        self.fruit.iter()
+            .map(|f| f.pos)
+            .min_by(|a, b| na::distance_squared(a, &head)
+                .partial_cmp(&na::distance_squared(b, &head)).unwrap())
+            .unwrap_or(head)
+    }
+
+    // sent before the audio queue is played, as that's where this frame's
+    // events are
+    #[cfg(feature = "telemetry")]
+    fn send_telemetry(&mut self) {
+        let state = match self.play_state {
+            PlayState::Space => "title",
+            PlayState::Countdown => "countdown",
+            PlayState::Play => "play",
+            PlayState::Paused => "paused",
+            PlayState::Settings => "settings",
+            PlayState::LevelComplete => "level complete",
+            PlayState::Connecting => "connecting",
+            PlayState::Simulation => "simulation",
+            PlayState::Dead => "dead",
+            PlayState::GameOver => "game over"
+        };
+        let head = &self.snake.head;
+        self.telemetry.send(&telemetry::Frame {
+            state,
+            x: head.pos.x,
+            y: head.pos.y,
+            angle: head.angle,
+            speed: head.speed,
+            length: self.snake.current_length,
+            score: self.score.score,
+            events: self.audio.queued().iter()
+                .map(|s| telemetry::event_name(*s)).collect()
+        });
+    }
+
+    // remote commands say what to hold rather than pressing and releasing
+    #[cfg(feature = "telemetry")]
+    fn command(&mut self, ctx: &mut Context, command: Command) {
+        match command {
+            Command::Steer(Direction::Straight) =>
+                self.release(Action::Steer(Direction::Straight)),
+            Command::Steer(direction) =>
+                self.act(ctx, Action::Steer(direction)),
+            Command::Throttle(Speed::Coast) =>
+                self.release(Action::Throttle(Speed::Coast)),
+            Command::Throttle(speed) => self.act(ctx, Action::Throttle(speed)),
+            Command::Pause => self.act(ctx, Action::Pause),
+            Command::Confirm => self.act(ctx, Action::Confirm)
+        }
+    }
+
+    // running a signal throws on the emergency brake, and costs a player
+    // points
+    fn check_signals(&mut self) {
+        if overran(&mut self.signals, 0, &mut self.snake) {
+            self.score.score = (self.score.score - 20).max(0);
+        }
+        if let Some(second) = self.second.as_mut() {
+            if overran(&mut self.signals, 1, second) {
+                self.second_score = (self.second_score - 20).max(0);
+            }
+        }
+        if let Some(rival) = self.rival.as_mut() {
+            overran(&mut self.signals, stations::RIVAL, &mut rival.snake);
+        }
+    }
+}
+
+impl ggez::event::EventHandler for State {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        #[cfg(feature = "telemetry")]
+        for command in self.telemetry.commands() {
+            self.command(ctx, command);
+        }
+
+        self.poll_connection();
+        while timer::check_update_time(ctx, UPDATE_RATE) {
+            if self.session.is_some() {
+                self.online_step(ctx)?;
+            } else {
+                self.step(ctx)?;
+            }
+        }
+
+        #[cfg(feature = "telemetry")]
+        self.send_telemetry();
+        self.audio.play()?;
+        self.update_music();
+
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context,
+                      keycode: keyboard::KeyCode,
+                      _keymods: keyboard::KeyMods,
+                      repeat: bool) {
+        #[cfg(feature = "profiling")]
+        if keycode == keyboard::KeyCode::F3 && !repeat {
+            self.profiler.visible = !self.profiler.visible;
+        }
+        if let Some(action) = self.second_action(keycode) {
+            if !repeat {
+                self.hold_second(action, true);
+            }
+            return;
+        }
+        if let Some(action) = self.config.keys.action(keycode) {
+            if !repeat {
+                self.act(ctx, action);
+            }
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context,
+                    keycode: keyboard::KeyCode,
+                    _keymods: keyboard::KeyMods) {
+        if let Some(action) = self.second_action(keycode) {
+            self.hold_second(action, false);
+            return;
+        }
+        if let Some(action) = self.config.keys.action(keycode) {
+            self.release(action);
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context,
+                               _button: event::MouseButton,
+                               x: f32, y: f32) {
+        self.touch.visible = true;
+        let p = window_to_screen(ctx, x, y);
+        match self.touch.hit(p) {
+            Some(action) => {
+                self.touch.held = Some(action);
+                self.act(ctx, action);
+            },
+            None => self.act(ctx, Action::Confirm)
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context,
+                             _button: event::MouseButton,
+                             _x: f32, _y: f32) {
+        if let Some(action) = self.touch.held.take() {
+            self.release(action);
+        }
+    }
+
+    // sliding a finger from one button to another swaps what's held
+    fn mouse_motion_event(&mut self, ctx: &mut Context,
+                          x: f32, y: f32, _dx: f32, _dy: f32) {
+        if let Some(held) = self.touch.held {
+            let hit = self.touch.hit(window_to_screen(ctx, x, y));
+            if hit != Some(held) {
+                self.release(held);
+                self.touch.held = hit;
+                if let Some(action) = hit {
+                    self.act(ctx, action);
+                }
+            }
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context,
+                                 button: event::Button,
+                                 _id: event::GamepadId) {
+        if let Some(action) = input::button_action(button) {
+            self.act(ctx, action);
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context,
+                               button: event::Button,
+                               _id: event::GamepadId) {
+        if let Some(action) = input::button_action(button) {
+            self.release(action);
+        }
+    }
+
+    fn gamepad_axis_event(&mut self, ctx: &mut Context,
+                          axis: event::Axis, value: f32,
+                          _id: event::GamepadId) {
+        if axis == event::Axis::LeftStickX {
+            match input::stick_direction(value) {
+                Direction::Straight => self.release(
+                    Action::Steer(Direction::Straight)),
+                direction => self.act(ctx, Action::Steer(direction))
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        profile!("draw");
+        graphics::clear(ctx, (0.1, 0.2, 0.3, 1.0).into());
+
+        // hold still while paused rather than flicking between steps
+        let dead = self.play_state == PlayState::Dead;
+        let alpha = match self.play_state {
+            PlayState::Paused | PlayState::Settings | PlayState::Countdown |
+                PlayState::LevelComplete | PlayState::Dead => 1.0,
+            _ => timer::duration_to_f64(timer::remaining_update_time(ctx))
+                as f32 * UPDATE_RATE as f32
+        };
+        if self.play_state == PlayState::Simulation {
+            self.draw_simulation(ctx)?;
+            #[cfg(feature = "profiling")]
+            self.profiler.draw(ctx, &self.pool_usage())?;
+            return graphics::present(ctx);
+        }
+        // computer snakes in place of the player's behind the title
+        let attract = self.play_state == PlayState::Space &&
+            self.simulation.is_some();
+        if let Some(simulation) = &mut self.simulation {
+            simulation.draw(ctx, &self.config.display,
+                            &mut self.fruit_sprites, alpha)?;
+        }
+        if !self.on_title() {
+            if let Some(ghost) = &mut self.ghost {
+                ghost.draw(ctx, &self.config.display, alpha)?;
+            }
+        }
+        // whoever crashed is drawn as the explosion instead
+        if !attract && (!dead || self.loser == Some(1)) {
+            let look_at = self.nearest_fruit(self.snake.head.pos);
+            self.snake.draw(ctx, &self.config.display, look_at, alpha)?;
+        }
+        if !self.on_title() && (!dead || self.loser == Some(0)) {
+            if let Some(head) = self.second.as_ref().map(|s| s.head.pos) {
+                let look_at = self.nearest_fruit(head);
+                if let Some(second) = &mut self.second {
+                    second.draw(ctx, &self.config.display, look_at, alpha)?;
+                }
+            }
+        }
+        if !dead {
+            if let Some(rival) = &mut self.rival {
+                rival.draw(ctx, &self.config.display, alpha)?;
+            }
+        }
+
+        self.explosion.draw(ctx, &self.config.display)?;
+
+        for (i, station) in self.stations.iter_mut().enumerate() {
+            let destination = self.boarded_at[0].is_some_and(|b| b != i);
+            station.draw(ctx, destination)?;
+        }
+
+        for signal in self.signals.iter_mut() {
+            signal.draw(ctx)?;
+        }
+
+        if self.walled() {
+            graphics::draw(ctx, &self.walls, graphics::DrawParam::new())?;
+        }
+        self.obstacles.draw(ctx, &self.config.display)?;
+        if let Some(hill) = &self.hill {
+            if !self.on_title() {
+                hill.draw(ctx)?;
+            }
+        }
+        for debris in self.debris.iter() {
+            debris.draw(ctx, &self.config.display)?;
+        }
+        let centre = match (self.swirl.player(), &self.second) {
+            (1, Some(second)) => second.head.pos,
+            _ => self.snake.head.pos
+        };
+        self.swirl.draw(ctx, centre, self.config.display.reduced_motion)?;
+        if !attract {
+            self.fruit_sprites.draw(ctx, &self.config.display, &self.fruit)?;
+        }
+        if let Some(chain) = &self.chain {
+            chain.draw(ctx, &self.config.display, &mut self.fruit_sprites,
+                       &self.chain_numbers)?;
+        }
+        for popup in self.popups.iter() {
+            popup.draw(ctx)?;
+        }
+
+        if self.play_state == PlayState::Space ||
+                self.play_state == PlayState::Connecting {
+            self.draw_title(ctx)?;
+        }
+
+        if !self.config.display.reduced_motion {
+            self.speed_lines.draw(ctx)?;
+        }
+
+        if self.play_state == PlayState::GameOver {
+            self.draw_game_over(ctx)?;
+        }
+
+        // a crash with lives left isn't the end of the run
+        let ended = if dead && self.respawning() {
+            None
+        } else {
+            self.last_run.as_ref()
+        };
+        if let (PlayState::Space | PlayState::Dead | PlayState::GameOver,
+                Some(results)) = (self.play_state, ended) {
+            let (w, h) = screen_size(ctx);
+            let top_speed = self.config.rules.top_speed();
+            // the graph and then a little map of where the snake went
+            let map_w = 120.0 * w / h;
+            let left = w / 2.0 - (400.0 + map_w) / 2.0;
+            results.graph.draw(ctx,
+                graphics::Rect::new(left, h - 190.0, 380.0, 120.0),
+                top_speed)?;
+            results.graph.draw_path(ctx,
+                graphics::Rect::new(left + 400.0, h - 190.0, map_w, 120.0),
+                (w, h), top_speed)?;
+            let note = match &self.card_saved {
+                Some(path) => format!("saved {}", path),
+                None => "F12 saves a score card".to_string()
+            };
+            let text = graphics::Text::new(
+                (note, graphics::Font::default(), 16.0));
+            graphics::draw(ctx, &text, graphics::DrawParam::new()
+                .dest(na::Point2::new(left, h - 64.0)))?;
+        }
+
+        self.score.draw(ctx)?;
+        self.draw_hud(ctx)?;
+
+        self.touch.draw(ctx)?;
+
+        if self.play_state == PlayState::Paused {
+            self.draw_pause_menu(ctx)?;
+        }
+
+        if self.play_state == PlayState::LevelComplete {
+            self.draw_level_complete(ctx)?;
+        }
+
+        if self.play_state == PlayState::Countdown {
+            self.draw_countdown(ctx)?;
+        }
+
+        if dead && self.second.is_some() {
+            let (w, h) = screen_size(ctx);
+            let text = graphics::Text::new((
+                self.verdict(), graphics::Font::default(), 48.0));
+            let (tw, _) = text.dimensions(ctx);
+            graphics::draw(ctx, &text, graphics::DrawParam::new()
+                .dest(na::Point2::new((w - tw as f32) / 2.0, h / 3.0)))?;
+        }
+
+        if self.play_state == PlayState::Settings {
+            self.settings.draw(ctx, &self.config)?;
+        }
+
+        self.draw_notice(ctx)?;
+
+        #[cfg(feature = "profiling")]
+        self.profiler.draw(ctx, &self.pool_usage())?;
+
+        profile!("present");
+        graphics::present(ctx)?;
+        Ok(())
+    }
+}
+
+// brakes `snake` hard if it's run any signal, returning whether it did
+fn overran(signals: &mut [Signal], index: usize, snake: &mut Snake) -> bool {
+    let mut overran = false;
+    for signal in signals.iter_mut() {
+        if signal.overrun(index, snake.head.pos, snake.head.speed) {
+            snake.head.speed = 0.0;
+            snake.flash.trigger(graphics::WHITE, 0.3);
+            overran = true;
+        }
+    }
+    overran
+}
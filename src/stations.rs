@@ -0,0 +1,172 @@
+use ggez::{graphics, Context, GameResult};
+use ggez::nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+use crate::UPDATE_RATE;
+
+// the two players and the rival, each tracked on their own in a zone
+pub const SNAKES: usize = 3;
+pub const RIVAL: usize = 2;
+
+// an area a snake can be detected stopping in
+pub struct Zone {
+    rect: graphics::Rect,
+    dwell: [i32; SNAKES],
+    inside: [bool; SNAKES]
+}
+
+// what a zone's noticed of each snake so far, for saving
+#[derive(Deserialize, Serialize)]
+pub struct SavedZone {
+    dwell: [i32; SNAKES],
+    inside: [bool; SNAKES]
+}
+
+impl Zone {
+    pub fn new(rect: graphics::Rect) -> Zone {
+        Zone {
+            rect,
+            dwell: [0; SNAKES],
+            inside: [false; SNAKES]
+        }
+    }
+
+    pub fn save(&self) -> SavedZone {
+        SavedZone {
+            dwell: self.dwell,
+            inside: self.inside
+        }
+    }
+
+    pub fn restore(&mut self, saved: SavedZone) {
+        self.dwell = saved.dwell;
+        self.inside = saved.inside;
+    }
+
+    pub fn centre(&self) -> na::Point2<f32> {
+        na::Point2::new(self.rect.x + self.rect.w / 2.0,
+                        self.rect.y + self.rect.h / 2.0)
+    }
+
+    // true on the step `snake`'s head crosses into the zone
+    pub fn enter(&mut self, snake: usize, pos: na::Point2<f32>) -> bool {
+        let was_inside = self.inside[snake];
+        self.inside[snake] = self.rect.contains(pos);
+        self.inside[snake] && !was_inside
+    }
+
+    // true on the one step when `snake`'s head has been stopped inside for
+    // `steps`, not again until it leaves or moves off
+    pub fn dwell(&mut self, snake: usize, pos: na::Point2<f32>, speed: f32,
+                 steps: i32) -> bool {
+        if speed < 0.1 && self.rect.contains(pos) {
+            self.dwell[snake] += 1;
+            self.dwell[snake] == steps
+        } else {
+            self.dwell[snake] = 0;
+            false
+        }
+    }
+}
+
+pub struct Station {
+    pub zone: Zone,
+    mesh: graphics::Mesh
+}
+
+impl Station {
+    pub fn new(ctx: &mut Context,
+               centre: na::Point2<f32>) -> GameResult<Station> {
+        let (w, h) = (120.0, 40.0);
+        let rect = graphics::Rect::new(centre.x - w / 2.0, centre.y - h / 2.0,
+                                       w, h);
+        let mesh = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::fill(), rect, graphics::WHITE)?;
+        Ok(Station {
+            zone: Zone::new(rect),
+            mesh
+        })
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, destination: bool) -> GameResult {
+        let color = if destination {
+            graphics::Color::new(0.3, 0.8, 0.3, 0.6)
+        } else {
+            graphics::Color::new(0.6, 0.6, 0.6, 0.4)
+        };
+        graphics::draw(ctx, &self.mesh,
+                       graphics::DrawParam::new().color(color))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum Aspect {
+    Green,
+    Yellow,
+    Red
+}
+
+pub struct Signal {
+    pub zone: Zone,
+    mesh: graphics::Mesh,
+    pub aspect: Aspect,
+    // steps until the next aspect
+    pub timer: i32
+}
+
+impl Signal {
+    pub fn new(ctx: &mut Context,
+               centre: na::Point2<f32>) -> GameResult<Signal> {
+        let size = 60.0;
+        let rect = graphics::Rect::new(centre.x - size / 2.0,
+                                       centre.y - size / 2.0, size, size);
+        let mesh = graphics::Mesh::new_rectangle(ctx,
+            graphics::DrawMode::stroke(4.0), rect, graphics::WHITE)?;
+        Ok(Signal {
+            zone: Zone::new(rect),
+            mesh,
+            aspect: Aspect::Green,
+            timer: 5 * UPDATE_RATE as i32
+        })
+    }
+
+    pub fn update(&mut self) {
+        self.timer -= 1;
+        if self.timer <= 0 {
+            let second = UPDATE_RATE as i32;
+            let (aspect, timer) = match self.aspect {
+                Aspect::Green => (Aspect::Yellow, second * 3 / 2),
+                Aspect::Yellow => (Aspect::Red, second * 3),
+                Aspect::Red => (Aspect::Green, second * 5)
+            };
+            self.aspect = aspect;
+            self.timer = timer;
+        }
+    }
+
+    pub fn speed_limit(&self) -> f32 {
+        match self.aspect {
+            Aspect::Green => f32::MAX,
+            Aspect::Yellow => 2.0,
+            // crawling in at the starting speed is allowed
+            Aspect::Red => 1.0
+        }
+    }
+
+    // true if `snake`'s head has just run into the block faster than
+    // allowed
+    pub fn overrun(&mut self, snake: usize, pos: na::Point2<f32>,
+                   speed: f32) -> bool {
+        self.zone.enter(snake, pos) && speed > self.speed_limit()
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let color = match self.aspect {
+            Aspect::Green => graphics::Color::new(0.2, 0.9, 0.2, 0.8),
+            Aspect::Yellow => graphics::Color::new(0.9, 0.8, 0.1, 0.8),
+            Aspect::Red => graphics::Color::new(0.9, 0.1, 0.1, 0.8)
+        };
+        graphics::draw(ctx, &self.mesh,
+                       graphics::DrawParam::new().color(color))
+    }
+}
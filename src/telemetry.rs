@@ -0,0 +1,136 @@
+use ggez::{GameError, GameResult};
+use serde::{Deserialize, Serialize};
+use std::{io, net, time};
+use tungstenite::{Message, WebSocket};
+
+use crate::audio::Sound;
+use crate::input::{Direction, Speed};
+
+// what every client is sent once a frame
+#[derive(Serialize)]
+pub struct Frame {
+    pub state: &'static str,
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub speed: f32,
+    pub length: f32,
+    pub score: i32,
+    pub events: Vec<&'static str>
+}
+
+// what a client can send to drive the game with --remote-control, as
+// JSON like {"steer": "left"}, {"throttle": "coast"} or "pause"
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Command {
+    Steer(Direction),
+    Throttle(Speed),
+    Pause,
+    Confirm
+}
+
+// a local websocket for stream overlays and stats tools to watch the game
+// through; it's polled from the game loop so nothing here blocks for long
+pub struct Telemetry {
+    listener: Option<net::TcpListener>,
+    clients: Vec<WebSocket<net::TcpStream>>,
+    // commands are only listened to when asked for
+    remote_control: bool
+}
+
+impl Telemetry {
+    // nothing listens unless a port was asked for, and only on this machine
+    pub fn new(port: Option<u16>,
+               remote_control: bool) -> GameResult<Telemetry> {
+        let listener = match port {
+            Some(port) => {
+                let listener = net::TcpListener::bind(("127.0.0.1", port))
+                    .map_err(io_error)?;
+                listener.set_nonblocking(true).map_err(io_error)?;
+                Some(listener)
+            },
+            None => None
+        };
+        Ok(Telemetry {
+            listener,
+            clients: Vec::<WebSocket<net::TcpStream>>::new(),
+            remote_control
+        })
+    }
+
+    fn accept(&mut self) {
+        let listener = match &self.listener {
+            Some(listener) => listener,
+            None => return
+        };
+        while let Ok((stream, _)) = listener.accept() {
+            // a client gets a second to finish the handshake
+            let timeout = Some(time::Duration::from_secs(1));
+            if stream.set_nonblocking(false).is_err() ||
+                    stream.set_read_timeout(timeout).is_err() {
+                continue;
+            }
+            if let Ok(ws) = tungstenite::accept(stream) {
+                if ws.get_ref().set_nonblocking(true).is_ok() {
+                    self.clients.push(ws);
+                }
+            }
+        }
+    }
+
+    // clients that have gone away are dropped
+    pub fn send(&mut self, frame: &Frame) {
+        self.accept();
+        if self.clients.is_empty() {
+            return;
+        }
+        let text = match serde_json::to_string(frame) {
+            Ok(text) => text,
+            Err(_) => return
+        };
+        self.clients.retain_mut(|ws| {
+            match ws.send(Message::Text(text.clone())) {
+                Ok(()) => true,
+                Err(tungstenite::Error::Io(e)) =>
+                    e.kind() == io::ErrorKind::WouldBlock,
+                Err(_) => false
+            }
+        });
+    }
+
+    // everything clients have sent since last time; without remote control
+    // it's read and thrown away, which also keeps pings answered
+    pub fn commands(&mut self) -> Vec<Command> {
+        let mut commands = Vec::<Command>::new();
+        let remote_control = self.remote_control;
+        self.clients.retain_mut(|ws| loop {
+            match ws.read() {
+                Ok(Message::Text(text)) if remote_control => {
+                    if let Ok(command) = serde_json::from_str(&text) {
+                        commands.push(command);
+                    }
+                },
+                Ok(_) => {},
+                Err(tungstenite::Error::Io(e)) =>
+                    break e.kind() == io::ErrorKind::WouldBlock,
+                Err(_) => break false
+            }
+        });
+        commands
+    }
+}
+
+pub fn event_name(sound: Sound) -> &'static str {
+    match sound {
+        Sound::Pickup => "pickup",
+        Sound::Crash => "crash",
+        Sound::Pop => "pop",
+        Sound::Start => "start",
+        Sound::Title => "title"
+    }
+}
+
+fn io_error(e: io::Error) -> GameError {
+    GameError::ConfigError(format!("telemetry: {}", e))
+}